@@ -0,0 +1,43 @@
+//! Times full solves of 25x25 puzzles, run with `cargo bench`.
+
+use std::time::{Duration, Instant};
+
+use sudoku::prelude::*;
+use sudoku::state::State;
+use sudoku::random::Random;
+
+const SUB_SIZE: Coord = Coord { x: 5, y: 5 };
+const PUZZLES:  u64 = 10;
+///Out of every 100 nodes of the solution, how many are kept as givens.
+const KEEP:     usize = 52;
+
+fn main() {
+    let mut solution = State::new(SUB_SIZE);
+    let start = Instant::now();
+    assert!(solution.full_solve());
+    report("empty grid", 1, start.elapsed());
+
+    let size = solution.size() as i32;
+    let mut total = Duration::ZERO;
+
+    for seed in 0..PUZZLES {
+        let mut random = Random::new(seed);
+        let mut state = State::new(SUB_SIZE);
+
+        for pos in CoordIter::new(Coord { x: 0, y: 0 }, Coord { x: size, y: size }) {
+            if random.below(100) < KEEP {
+                state.set(pos, solution.nodes().get(pos).get_num().unwrap());
+            }
+        }
+
+        let start = Instant::now();
+        assert!(state.full_solve());
+        total += start.elapsed();
+    }
+
+    report("partial grids", PUZZLES as u32, total);
+}
+
+fn report(name: &str, count: u32, time: Duration) {
+    println!("{:<14} {:>4} solves {:>10.2?} each", name, count, time / count);
+}
@@ -0,0 +1,428 @@
+use std::io::Read;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::prelude::*;
+use crate::formats::{Puzzle, Format, load_all};
+use crate::state::{State, Chess};
+use crate::generator::{Generator, Symmetry};
+use crate::random::Random;
+use crate::rating::Difficulty;
+use crate::dlx::Dlx;
+
+pub const EXIT_SOLVED:        i32 = 0;
+pub const EXIT_STUCK:         i32 = 1;
+pub const EXIT_CONTRADICTION: i32 = 2;
+pub const EXIT_ERROR:         i32 = 3;
+
+///How many puzzles generate makes looking for one of the wanted difficulty.
+const DIFFICULTY_ATTEMPTS: usize = 100;
+
+pub const SOLVE_USAGE: &str = "Usage: sudoku solve [FILE] [--level LEVEL] [--format FORMAT] [--json]
+    FILE               File of puzzles to solve, read from stdin if missing or '-'.
+    --level LEVEL      basic, exclusive, logic, full or exact. Defaults to full.
+    --format FORMAT    line, ss, sdk, sdm, jigsaw or variant for stdin and the printed grids. Defaults to line.
+                       Files are read in the format of their extension.
+    --json             Print a JSON report for each puzzle instead of the grid.
+
+Exit codes: 0 every puzzle was solved, 1 a solver got stuck, 2 a puzzle has no solution, 3 bad arguments or input.";
+
+pub const GENERATE_USAGE: &str = "Usage: sudoku generate [--block WxH] [--seed N] [--symmetry SYMMETRY] [--difficulty DIFFICULTY] [--count N] [--format FORMAT]
+                       [--diagonal] [--windows] [--anti-knight] [--anti-king]
+    --block WxH            Width and height of the blocks. Defaults to 3x3.
+    --seed N               Seed for the random numbers, the same seed always makes the same puzzles.
+                           Picked from the time and printed to stderr if missing.
+    --symmetry SYMMETRY    none, rotational, mirror or diagonal. Defaults to rotational.
+    --difficulty DIFFICULTY
+                           easy, medium, hard, expert or needs-guessing. Defaults to any difficulty.
+    --count N              Number of puzzles to make. Defaults to 1.
+    --format FORMAT        line, ss, sdk, sdm, jigsaw or variant. Defaults to line.
+                           Only the variant format can hold the constraints below.
+    --diagonal             Both diagonals need every number once too.
+    --windows              The windows of a windoku need every number once too, only for square blocks.
+    --anti-knight          Nodes a knight's move apart can't hold the same number.
+    --anti-king            Nodes a king's move apart can't hold the same number.";
+
+///The solvers that can be run without the interface.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Level {
+    ///Loop basic solve.
+    Basic,
+    ///Loop exclusive solve.
+    Exclusive,
+    ///Full solve without guessing.
+    Logic,
+    ///Full solve with guessing.
+    Full,
+    ///The exact cover solver, fast but without guess levels. Killer puzzles and puzzles with
+    ///edge marks use full solve instead.
+    Exact,
+}
+
+impl Level {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "basic"     => { Some(Self::Basic) }
+            "exclusive" => { Some(Self::Exclusive) }
+            "logic"     => { Some(Self::Logic) }
+            "full"      => { Some(Self::Full) }
+            "exact"     => { Some(Self::Exact) }
+            _ => { None }
+        }
+    }
+
+    pub fn solve(&self, state: &mut State) -> Outcome {
+        match self {
+            Self::Basic     => { state.loop_basic() }
+            Self::Exclusive => { state.loop_exclusive() }
+            Self::Logic     => { state.full_solve_no_guessing(); }
+            Self::Full      => {
+                // a failed full solve has tried every guess and undone them, so the puzzle has no solution.
+                if !state.full_solve() { return Outcome::Contradiction }
+            }
+            // the exact cover search only sees a cage's sum once its nodes are nearly filled in,
+            // and an edge mark once both its nodes are, which is slow with few givens.
+            Self::Exact if !Dlx::suits(state) => {
+                return Self::Full.solve(state)
+            }
+            Self::Exact     => {
+                match Dlx::new(state).solve() {
+                    Some(grid) => {
+                        for pos in CoordIter::new(Coord { x: 0, y: 0 }, grid.size()) {
+                            if !state.nodes().get(pos).is_found() {
+                                state.set(pos, *grid.get(pos));
+                            }
+                        }
+                    }
+                    None => { return Outcome::Contradiction }
+                }
+            }
+        }
+
+        Outcome::of(state)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum Outcome {
+    Solved,
+    Stuck,
+    Contradiction,
+}
+
+impl Outcome {
+    pub fn of(state: &State) -> Self {
+        if state.has_conflicts() { Self::Contradiction }
+        else if state.is_done()  { Self::Solved }
+        else                     { Self::Stuck }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Solved        => { "solved" }
+            Self::Stuck         => { "stuck" }
+            Self::Contradiction => { "contradiction" }
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Solved        => { EXIT_SOLVED }
+            Self::Stuck         => { EXIT_STUCK }
+            Self::Contradiction => { EXIT_CONTRADICTION }
+        }
+    }
+}
+
+///The result of solving one puzzle.
+pub struct Report {
+    pub outcome: Outcome,
+    pub guesses: usize,
+    pub time:    Duration,
+    ///Every solved node at the end of the solve.
+    pub grid:    Puzzle,
+}
+
+impl Report {
+    pub fn new(puzzle: &Puzzle, level: Level) -> Self {
+        let start = Instant::now();
+        let mut state = puzzle.to_state();
+        let outcome = level.solve(&mut state);
+
+        Self {
+            outcome,
+            guesses: state.guess_count(),
+            time:    start.elapsed(),
+            grid:    Puzzle::grid(&state),
+        }
+    }
+
+    ///number is the position of the puzzle in the input counting from 1.
+    pub fn json(&self, number: usize) -> String {
+        format!(
+            "{{\"puzzle\": {}, \"status\": \"{}\", \"guesses\": {}, \"time_ms\": {:.3}, \"grid\": \"{}\"}}",
+            number,
+            self.outcome.name(),
+            self.guesses,
+            self.time.as_secs_f64() * 1000.0,
+            Format::Line.write(std::slice::from_ref(&self.grid)).trim_end(),
+        )
+    }
+}
+
+///Runs the solve command and returns the exit code.
+pub fn solve(args: &[String]) -> i32 {
+    match solve_args(args) {
+        Ok(code) => { code }
+        Err(msg) => {
+            eprintln!("{}\n\n{}", msg, SOLVE_USAGE);
+            EXIT_ERROR
+        }
+    }
+}
+
+fn solve_args(args: &[String]) -> Result<i32, String> {
+    let mut input = None;
+    let mut level = Level::Full;
+    let mut format = Format::Line;
+    let mut json = false;
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", SOLVE_USAGE);
+                return Ok(EXIT_SOLVED)
+            }
+            "--level" => {
+                let name = args.next().ok_or("--level needs a solver level")?;
+                level = Level::parse(name).ok_or(format!("Unknown solver level \"{}\"", name))?;
+            }
+            "--format" => {
+                let name = args.next().ok_or("--format needs a format")?;
+                format = parse_format(name).ok_or(format!("Unknown format \"{}\"", name))?;
+            }
+            "--json" => { json = true }
+            _ if input.is_none() => { input = Some(arg.as_str()) }
+            _ => { return Err(format!("Unexpected argument \"{}\"", arg)) }
+        }
+    }
+
+    let puzzles = read_input(input, format)?;
+    let mut code = EXIT_SOLVED;
+    let mut worst = Outcome::Solved;
+
+    for (i, puzzle) in puzzles.iter().enumerate() {
+        let report = Report::new(puzzle, level);
+
+        if json {
+            println!("{}", report.json(i + 1));
+        }
+        else {
+            print!("{}", format.write(std::slice::from_ref(&report.grid)));
+
+            if report.outcome != Outcome::Solved {
+                eprintln!("Puzzle {}: {}", i + 1, report.outcome.name());
+            }
+        }
+
+        if report.outcome > worst {
+            worst = report.outcome;
+            code = worst.exit_code();
+        }
+    }
+
+    Ok(code)
+}
+
+///Runs the generate command and returns the exit code.
+pub fn generate(args: &[String]) -> i32 {
+    match generate_args(args) {
+        Ok(code) => { code }
+        Err(msg) => {
+            eprintln!("{}\n\n{}", msg, GENERATE_USAGE);
+            EXIT_ERROR
+        }
+    }
+}
+
+fn generate_args(args: &[String]) -> Result<i32, String> {
+    let mut sub_size = Coord { x: 3, y: 3 };
+    let mut seed = None;
+    let mut symmetry = Symmetry::Rotational;
+    let mut difficulty = None;
+    let mut count = 1;
+    let mut format = Format::Line;
+    let mut diagonal = false;
+    let mut windows = false;
+    let mut chess = Vec::new();
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", GENERATE_USAGE);
+                return Ok(EXIT_SOLVED)
+            }
+            "--block" => {
+                let text = args.next().ok_or("--block needs a block size")?;
+                sub_size = parse_block(text).ok_or(format!("Invalid block size \"{}\"", text))?;
+            }
+            "--seed" => {
+                let text = args.next().ok_or("--seed needs a number")?;
+                seed = Some(text.parse().map_err(|_| format!("Invalid seed \"{}\"", text))?);
+            }
+            "--symmetry" => {
+                let name = args.next().ok_or("--symmetry needs a symmetry")?;
+                symmetry = Symmetry::parse(name).ok_or(format!("Unknown symmetry \"{}\"", name))?;
+            }
+            "--difficulty" => {
+                let name = args.next().ok_or("--difficulty needs a difficulty")?;
+                difficulty = Some(Difficulty::parse(name).ok_or(format!("Unknown difficulty \"{}\"", name))?);
+            }
+            "--count" => {
+                let text = args.next().ok_or("--count needs a number")?;
+                count = text.parse().map_err(|_| format!("Invalid count \"{}\"", text))?;
+            }
+            "--format" => {
+                let name = args.next().ok_or("--format needs a format")?;
+                format = parse_format(name).ok_or(format!("Unknown format \"{}\"", name))?;
+            }
+            "--diagonal" => { diagonal = true }
+            "--windows" => { windows = true }
+            "--anti-knight" | "--anti-king" => {
+                chess.extend(Chess::parse(&arg[2..]).filter(|x| !chess.contains(x)));
+            }
+            _ => { return Err(format!("Unexpected argument \"{}\"", arg)) }
+        }
+    }
+
+    if windows && sub_size.x != sub_size.y { return Err("--windows needs square blocks".to_string()) }
+
+    let mut check = Puzzle::new(sub_size);
+    check.diagonal = diagonal;
+    check.windows = windows;
+    check.chess = chess.clone();
+
+    if !format.holds(&check) {
+        return Err(format!("The {} format can't hold the constraints, use --format variant", format.name()))
+    }
+
+    if Dlx::new(&check.to_state()).count(1) == 0 { return Err("No grid fits the constraints".to_string()) }
+
+    let seed = seed.unwrap_or_else(|| {
+        let seed = Random::seed_from_time();
+        eprintln!("seed: {}", seed);
+        seed
+    });
+
+    let mut generator = Generator::new(seed, symmetry);
+    generator.set_diagonal(diagonal);
+    generator.set_windows(windows);
+    generator.set_chess(&chess);
+
+    let mut puzzles = Vec::new();
+
+    for _ in 0..count {
+        let puzzle = match difficulty {
+            Some(target) => {
+                generator.generate_difficulty(sub_size, target, DIFFICULTY_ATTEMPTS)
+                    .ok_or(format!("Could not make a {} puzzle in {} attempts", target.name(), DIFFICULTY_ATTEMPTS))?
+            }
+            None => { generator.generate(sub_size).ok_or("No grid fits the constraints")? }
+        };
+
+        puzzles.push(puzzle);
+    }
+
+    print!("{}", format.write(&puzzles));
+
+    Ok(EXIT_SOLVED)
+}
+
+///Reads a block size written as WxH.
+pub fn parse_block(text: &str) -> Option<Coord> {
+    let (x, y) = text.split_once('x')?;
+    let block = Coord { x: x.parse().ok()?, y: y.parse().ok()? };
+
+    if block.x < 1 || block.y < 1 || block.x * block.y > 25 { return None }
+
+    Some(block)
+}
+
+///Reads the puzzles from the file, or from stdin in the given format if there isn't one.
+pub fn read_input(path: Option<&str>, format: Format) -> Result<Vec<Puzzle>, String> {
+    let puzzles = match path {
+        None | Some("-") => {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text).map_err(|err| format!("Could not read stdin: {}", err))?;
+
+            format.read(&text).map_err(|err| format!("Invalid puzzle: {}", err))?
+        }
+        Some(path) => {
+            load_all(Path::new(path)).map_err(|err| format!("Could not load \"{}\": {}", path, err))?
+        }
+    };
+
+    if puzzles.is_empty() { return Err("No puzzles found".to_string()) }
+
+    Ok(puzzles)
+}
+
+pub fn parse_format(name: &str) -> Option<Format> {
+    match name {
+        "line"   => { Some(Format::Line) }
+        "ss"     => { Some(Format::Ss) }
+        "sdk"    => { Some(Format::Sdk) }
+        "sdm"    => { Some(Format::Sdm) }
+        "jigsaw" => { Some(Format::Jigsaw) }
+        // killer and Kropki puzzles share the variant format, their files keep their own extensions.
+        "variant" | "killer" | "kropki" => { Some(Format::Variant) }
+        _ => { None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn solve_files() {
+        let dir = std::env::temp_dir();
+        let solvable = dir.join("sudoku_cli_solvable.txt");
+        let broken = dir.join("sudoku_cli_broken.txt");
+
+        std::fs::write(&solvable, "1...\n..1.\n.1..\n...1\n").unwrap();
+        std::fs::write(&broken, "11..\n....\n....\n....\n").unwrap();
+
+        for level in ["full", "exact"] {
+            let solvable = format!("{} --level {}", solvable.display(), level);
+            assert_eq!(solve_args(&args(&solvable)), Ok(EXIT_SOLVED));
+        }
+
+        assert_eq!(solve_args(&args(&format!("{} --json", broken.display()))), Ok(EXIT_CONTRADICTION));
+        assert!(solve_args(&args("--level guessing")).is_err());
+        assert!(solve_args(&args("one.txt two.txt")).is_err());
+
+        std::fs::remove_file(solvable).unwrap();
+        std::fs::remove_file(broken).unwrap();
+    }
+
+    #[test]
+    fn generate_constraints() {
+        assert_eq!(generate_args(&args("--block 2x2 --seed 1 --count 2")), Ok(EXIT_SOLVED));
+        assert_eq!(generate_args(&args("--block 2x2 --seed 1 --diagonal --format variant")), Ok(EXIT_SOLVED));
+
+        // the constraints need the variant format, windows need square blocks and some can't be met at all.
+        assert!(generate_args(&args("--block 2x2 --diagonal")).is_err());
+        assert!(generate_args(&args("--block 3x2 --windows --format variant")).is_err());
+        assert!(generate_args(&args("--block 2x2 --diagonal --anti-king --format variant")).is_err());
+        assert!(generate_args(&args("--block 2x2 --difficulty impossible")).is_err());
+    }
+}
@@ -0,0 +1,464 @@
+use crate::prelude::*;
+use crate::state::{State, Edge, Mark, sum_range, broken_mark};
+use crate::random::Random;
+
+///Exact cover solver using dancing links. Every node needs exactly one number, and every
+///region needs each number exactly once. Each (node, number) choice covers
+///one of each of those constraints. Regions smaller than the puzzle, like cages, only need each
+///number at most once, so their constraints are never picked to cover, and the same goes for
+///pairs of nodes kept apart by a chess move. Cage sums and edge marks are checked
+///as choices are made, but cage sums only cut the search short once a cage is nearly filled and
+///marks once both their nodes are picked, so killer and Kropki puzzles with few givens are
+///better left to full solve.
+///
+///Unlike full solve this doesn't show its work, it's meant for checking and making puzzles quickly.
+pub struct Dlx {
+    left:     Vec<usize>,
+    right:    Vec<usize>,
+    up:       Vec<usize>,
+    down:     Vec<usize>,
+    ///Header of the constraint each link belongs to.
+    header:   Vec<usize>,
+    ///Choice each link belongs to, unused for headers.
+    choice:   Vec<usize>,
+    ///Links left under each header.
+    count:    Vec<usize>,
+    choices:  Vec<(Coord, usize)>,
+    picked:   Vec<usize>,
+    size:     usize,
+    ///Which cage each node is in, and the sum and number of nodes each cage has left to fill.
+    cage_of:  Vec2D<Option<usize>>,
+    cages:    Vec<(usize, usize)>,
+    edges:    Vec<Edge>,
+    negative: Vec<Mark>,
+    ///The nodes across an edge mark from each node, every neighbour with negative marks.
+    linked:   Vec2D<Vec<Coord>>,
+    ///Number picked for each node so far, to check the edge marks.
+    values:   Vec2D<Option<usize>>,
+}
+
+///Link 0 is the root that the headers hang off.
+const ROOT: usize = 0;
+
+impl Dlx {
+    ///Builds the constraints for the state's size and block shape. Found nodes only allow their
+    ///number and unsolved nodes allow their remaining possibilities.
+    pub fn new(state: &State) -> Self {
+        let size = state.size();
+        let regions = state.regions();
+        let cells = size * size;
+
+        // peers that don't share a region, like nodes a knight's move apart, each get a constraint per number.
+        let mut pairs = Vec::new();
+        let mut node_pairs = Vec2D::new(Coord::same(size as i32), Vec::new());
+
+        for pos in CoordIter::new(Coord { x: 0, y: 0 }, Coord::same(size as i32)) {
+            for other in state.peers(pos) {
+                if (other.y, other.x) < (pos.y, pos.x) || state.node_regions(pos).any(|x| x.contains(*other)) { continue; }
+
+                node_pairs.get_mut(pos).push(pairs.len());
+                node_pairs.get_mut(*other).push(pairs.len());
+                pairs.push((pos, *other));
+            }
+        }
+
+        let first_pair = cells + regions.len() * size;
+        let headers = first_pair + pairs.len() * size;
+
+        let mut dlx = Self {
+            left:     Vec::new(),
+            right:    Vec::new(),
+            up:       Vec::new(),
+            down:     Vec::new(),
+            header:   Vec::new(),
+            choice:   Vec::new(),
+            count:    vec![0; headers + 1],
+            choices:  Vec::new(),
+            picked:   Vec::new(),
+            size,
+            cage_of:  Vec2D::new(Coord::same(size as i32), None),
+            cages:    Vec::new(),
+            edges:    state.edges().to_vec(),
+            negative: state.negative().to_vec(),
+            linked:   Vec2D::new(Coord::same(size as i32), Vec::new()),
+            values:   Vec2D::new(Coord::same(size as i32), None),
+        };
+
+        for i in 0..=headers {
+            dlx.left.push(if i == 0 { headers } else { i - 1 });
+            dlx.right.push(if i == headers { 0 } else { i + 1 });
+            dlx.up.push(i);
+            dlx.down.push(i);
+            dlx.header.push(i);
+            dlx.choice.push(0);
+        }
+
+        // which regions each node is in, to find the header for a (region, number) constraint.
+        let mut node_regions = Vec2D::new(Coord::same(size as i32), Vec::new());
+        for (i, region) in regions.iter().enumerate() {
+            for pos in &region.cells {
+                node_regions.get_mut(*pos).push(i);
+            }
+
+            if !region.is_full(size) {
+                for num in 0..size {
+                    dlx.optional(1 + cells + i * size + num);
+                }
+            }
+        }
+
+        for header in first_pair..headers {
+            dlx.optional(1 + header);
+        }
+
+        for (i, cage) in state.cages().iter().enumerate() {
+            for pos in &cage.cells {
+                *dlx.cage_of.get_mut(*pos) = Some(i);
+            }
+
+            dlx.cages.push((cage.sum, cage.cells.len()));
+        }
+
+        for pos in CoordIter::new(Coord { x: 0, y: 0 }, Coord::same(size as i32)) {
+            *dlx.linked.get_mut(pos) = state.linked(pos);
+        }
+
+        for pos in CoordIter::new(Coord { x: 0, y: 0 }, Coord::same(size as i32)) {
+            let node = state.nodes().get(pos);
+
+            let nums: Vec<usize> = match node.get_num() {
+                Some(num) => { vec![num] }
+                None => { node.possibilities().unwrap().iter().collect() }
+            };
+
+            for num in nums {
+                let mut links = vec![1 + (pos.y as usize * size + pos.x as usize)];
+
+                for region in node_regions.get(pos) {
+                    links.push(1 + cells + region * size + num);
+                }
+
+                for pair in node_pairs.get(pos) {
+                    links.push(1 + first_pair + pair * size + num);
+                }
+
+                dlx.add_choice(pos, num, &links);
+            }
+        }
+
+        dlx
+    }
+
+    ///Takes a constraint out of the header list so it's never picked to cover, choices still cover
+    ///it so it can't be covered twice.
+    fn optional(&mut self, header: usize) {
+        let (left, right) = (self.left[header], self.right[header]);
+
+        self.right[left] = right;
+        self.left[right] = left;
+        self.left[header] = header;
+        self.right[header] = header;
+    }
+
+    fn add_choice(&mut self, pos: Coord, num: usize, headers: &[usize]) {
+        let choice = self.choices.len();
+        let first = self.left.len();
+        self.choices.push((pos, num));
+
+        for (i, header) in headers.iter().enumerate() {
+            let link = first + i;
+
+            self.left.push(if i == 0 { first + headers.len() - 1 } else { link - 1 });
+            self.right.push(if i == headers.len() - 1 { first } else { link + 1 });
+            self.up.push(self.up[*header]);
+            self.down.push(*header);
+            self.header.push(*header);
+            self.choice.push(choice);
+
+            let above = self.up[*header];
+            self.down[above] = link;
+            self.up[*header] = link;
+            self.count[*header] += 1;
+        }
+    }
+
+    fn cover(&mut self, header: usize) {
+        self.right[self.left[header]] = self.right[header];
+        self.left[self.right[header]] = self.left[header];
+
+        let mut row = self.down[header];
+        while row != header {
+            let mut link = self.right[row];
+            while link != row {
+                self.down[self.up[link]] = self.down[link];
+                self.up[self.down[link]] = self.up[link];
+                self.count[self.header[link]] -= 1;
+                link = self.right[link];
+            }
+            row = self.down[row];
+        }
+    }
+
+    fn uncover(&mut self, header: usize) {
+        let mut row = self.up[header];
+        while row != header {
+            let mut link = self.left[row];
+            while link != row {
+                self.count[self.header[link]] += 1;
+                self.down[self.up[link]] = link;
+                self.up[self.down[link]] = link;
+                link = self.left[link];
+            }
+            row = self.up[row];
+        }
+
+        self.right[self.left[header]] = header;
+        self.left[self.right[header]] = header;
+    }
+
+    ///The constraint with the fewest choices left, None once everything is covered.
+    fn smallest(&self) -> Option<usize> {
+        let mut best = None;
+        let mut header = self.right[ROOT];
+
+        while header != ROOT {
+            if best.is_none_or(|x| self.count[header] < self.count[x]) {
+                best = Some(header);
+            }
+            header = self.right[header];
+        }
+
+        best
+    }
+
+    ///Searches for solutions until the limit is reached, keeping the first two found.
+    ///Choices are tried in a random order when a random generator is given.
+    fn search(&mut self, limit: usize, found: &mut Vec<Vec2D<usize>>, count: &mut usize, random: &mut Option<&mut Random>) {
+        let header = match self.smallest() {
+            Some(header) => { header }
+            None => {
+                *count += 1;
+                if found.len() < 2 {
+                    found.push(self.grid());
+                }
+                return
+            }
+        };
+
+        if self.count[header] == 0 { return }
+
+        let mut rows = Vec::new();
+        let mut row = self.down[header];
+        while row != header {
+            rows.push(row);
+            row = self.down[row];
+        }
+
+        if let Some(random) = random {
+            random.shuffle(&mut rows);
+        }
+
+        self.cover(header);
+
+        for row in rows {
+            let (pos, num) = self.choices[self.choice[row]];
+            if !self.fits_edges(pos, num) { continue; }
+
+            let cage = *self.cage_of.get(pos);
+
+            if let Some(cage) = cage {
+                if !self.fits_cage(cage, num) { continue; }
+
+                self.cages[cage].0 -= num + 1;
+                self.cages[cage].1 -= 1;
+            }
+
+            self.picked.push(self.choice[row]);
+            *self.values.get_mut(pos) = Some(num);
+
+            let mut link = self.right[row];
+            while link != row {
+                self.cover(self.header[link]);
+                link = self.right[link];
+            }
+
+            self.search(limit, found, count, random);
+
+            let mut link = self.left[row];
+            while link != row {
+                self.uncover(self.header[link]);
+                link = self.left[link];
+            }
+
+            self.picked.pop();
+            *self.values.get_mut(pos) = None;
+
+            if let Some(cage) = cage {
+                self.cages[cage].0 += num + 1;
+                self.cages[cage].1 += 1;
+            }
+
+            if *count >= limit { break; }
+        }
+
+        self.uncover(header);
+    }
+
+    ///True if the rest of the cage can still make its sum once the number is put in it.
+    fn fits_cage(&self, cage: usize, num: usize) -> bool {
+        let (sum, left) = self.cages[cage];
+        if num + 1 > sum { return false }
+
+        let (min, max) = sum_range(left - 1, self.size);
+        let rest = sum - num - 1;
+
+        min <= rest && rest <= max
+    }
+
+    ///True if the number breaks no edge mark with the neighbours picked so far.
+    fn fits_edges(&self, pos: Coord, num: usize) -> bool {
+        self.linked.get(pos).iter().all(|other| match self.values.get(*other) {
+            Some(value) => { broken_mark(&self.edges, &self.negative, pos, num, *other, *value, self.size as i32).is_none() }
+            None => { true }
+        })
+    }
+
+    fn grid(&self) -> Vec2D<usize> {
+        let mut grid = Vec2D::new(Coord::same(self.size as i32), 0);
+
+        for choice in &self.picked {
+            let (pos, num) = self.choices[*choice];
+            *grid.get_mut(pos) = num;
+        }
+
+        grid
+    }
+
+    ///Counts the ways the puzzle can be finished, stopping once the limit is reached.
+    ///Returns the count along with the first two solutions found.
+    pub fn solutions(&mut self, limit: usize) -> (usize, Vec<Vec2D<usize>>) {
+        let mut found = Vec::new();
+        let mut count = 0;
+
+        self.search(limit, &mut found, &mut count, &mut None);
+
+        (count, found)
+    }
+
+    ///False for killer puzzles and puzzles with edge marks, which full solve gets through faster.
+    pub fn suits(state: &State) -> bool {
+        state.cages().is_empty() && state.edges().is_empty() && state.negative().is_empty()
+    }
+
+    pub fn count(&mut self, limit: usize) -> usize {
+        self.solutions(limit).0
+    }
+
+    pub fn solve(&mut self) -> Option<Vec2D<usize>> {
+        self.solutions(1).1.pop()
+    }
+
+    ///Finds a solution picking between choices at random, used to make new grids.
+    pub fn solve_random(&mut self, random: &mut Random) -> Option<Vec2D<usize>> {
+        let mut found = Vec::new();
+        let mut count = 0;
+
+        self.search(1, &mut found, &mut count, &mut Some(random));
+
+        found.pop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::Puzzle;
+    use crate::generator::{Generator, Symmetry};
+    use crate::state::{Cage, Edge, Mark, Solutions};
+
+    fn corpus() -> Vec<Puzzle> {
+        let mut puzzles = vec![
+            Puzzle::parse("4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......").unwrap(),
+            Puzzle::parse("...2......46......7.9146.....1.8..6.8.36.14.7.9..3.5.....8657.3......69......4...").unwrap(),
+            // more than one solution.
+            Puzzle::parse("2.............62....1....7...6..8...3...9...7...6..4...4....8....52.............3").unwrap(),
+            // no solution, two 1s in the first row.
+            Puzzle::parse("11...............................................................................").unwrap(),
+        ];
+
+        // a small killer with no givens.
+        let mut killer = Puzzle::new(Coord { x: 2, y: 2 });
+        for (sum, y) in [(3, 0), (7, 1), (3, 2), (7, 3)] {
+            killer.cages.push(Cage::new(sum, [Coord { x: 0, y }, Coord { x: 1, y }]));
+        }
+        puzzles.push(killer);
+
+        // small Kropki puzzles, the second with negative white dots so every neighbour is checked.
+        let mut kropki = Puzzle::parse("1...............").unwrap();
+        kropki.edges.push(Edge::new(Mark::Black, Coord { x: 1, y: 0 }, Coord { x: 2, y: 0 }));
+        kropki.edges.push(Edge::new(Mark::White, Coord { x: 0, y: 1 }, Coord { x: 0, y: 2 }));
+        kropki.edges.push(Edge::new(Mark::Greater, Coord { x: 3, y: 3 }, Coord { x: 2, y: 3 }));
+        puzzles.push(kropki.clone());
+
+        kropki.negative.push(Mark::White);
+        puzzles.push(kropki);
+
+        for (seed, sub_size) in [(1, Coord { x: 2, y: 2 }), (2, Coord { x: 3, y: 2 }), (3, Coord { x: 2, y: 3 }), (4, Coord { x: 3, y: 3 })] {
+            puzzles.extend(Generator::new(seed, Symmetry::None).generate(sub_size));
+        }
+
+        puzzles
+    }
+
+    #[test]
+    fn matches_full_solve() {
+        for puzzle in corpus() {
+            let mut state = puzzle.to_state();
+            let (count, found) = Dlx::new(&state).solutions(2);
+
+            assert_eq!(count, state.count_solutions(2).0);
+            assert_eq!(found.len(), count);
+            assert_eq!(state.full_solve() && !state.has_conflicts(), count > 0);
+
+            if count == 1 {
+                for pos in CoordIter::new(Coord { x: 0, y: 0 }, found[0].size()) {
+                    assert_eq!(state.nodes().get(pos).get_num(), Some(*found[0].get(pos)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_solutions() {
+        for puzzle in corpus() {
+            let exact = Solutions::new(Dlx::new(&puzzle.to_state()).solutions(2).1);
+
+            match (exact, puzzle.to_state().solutions()) {
+                (Solutions::None, Solutions::None) => {}
+                (Solutions::Unique(a), Solutions::Unique(b)) => { assert_eq!(a.vec(), b.vec()) }
+                (Solutions::Multiple(..), Solutions::Multiple(..)) => {}
+                _ => { panic!("the solvers disagree on how many solutions there are") }
+            }
+        }
+
+        assert!(Dlx::suits(&corpus()[0].to_state()));
+        assert!(!Dlx::suits(&corpus()[4].to_state()));
+    }
+
+    #[test]
+    fn empty_grid() {
+        let state = State::new(Coord { x: 2, y: 2 });
+        assert_eq!(Dlx::new(&state).count(1000), 288);
+
+        let mut random = Random::new(5);
+        let grid = Dlx::new(&state).solve_random(&mut random).unwrap();
+        let puzzle = Puzzle::new(Coord { x: 2, y: 2 });
+        let mut filled = puzzle.to_state();
+
+        for pos in CoordIter::new(Coord { x: 0, y: 0 }, grid.size()) {
+            filled.set(pos, *grid.get(pos));
+        }
+
+        assert!(filled.is_done() && !filled.has_conflicts());
+    }
+}
@@ -0,0 +1,65 @@
+use std::fmt;
+
+use crate::state::BlockError;
+
+#[derive(Debug)]
+pub enum FormatError {
+    Io(std::io::Error),
+    ///A character that is neither a number, a blank or a separator.
+    BadChar(char),
+    ///A number that is too large for the size of the puzzle.
+    OutOfRange(char),
+    ///The number of cells found does not make a square puzzle that can be played.
+    BadCount(usize),
+    ///A line that could not be understood, counting from 1.
+    BadLine(usize),
+    ///A line turning on a constraint that needs square blocks, like windows, counting from 1.
+    NotSquare(usize),
+    ///Jigsaw blocks that can't be used.
+    Blocks(BlockError),
+    ///A puzzle with constraints the named format can't hold.
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(err) => {
+                write!(f, "{}", err)
+            }
+            Self::BadChar(c) => {
+                write!(f, "unexpected character '{}'", c)
+            }
+            Self::OutOfRange(c) => {
+                write!(f, "'{}' is too large for the size of the puzzle", c)
+            }
+            Self::BadCount(count) => {
+                write!(f, "found {} cells, which is not a supported puzzle size", count)
+            }
+            Self::BadLine(line) => {
+                write!(f, "line {} is not valid", line)
+            }
+            Self::NotSquare(line) => {
+                write!(f, "line {} needs square blocks", line)
+            }
+            Self::Blocks(err) => {
+                write!(f, "{}", err)
+            }
+            Self::Unsupported(format) => {
+                write!(f, "the {} format can't hold the puzzle's constraints", format)
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for FormatError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<BlockError> for FormatError {
+    fn from(err: BlockError) -> Self {
+        Self::Blocks(err)
+    }
+}
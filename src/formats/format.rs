@@ -0,0 +1,661 @@
+use std::fs;
+use std::path::Path;
+
+use crate::prelude::*;
+use crate::graphics::{num_char, char_num};
+use crate::state::{Region, Cage, Chess, Edge, Mark};
+use crate::hint;
+
+use super::{Puzzle, FormatError};
+
+///The text formats puzzles are exchanged in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    ///Every cell on a single line with '.' for blanks, one puzzle per line.
+    ///When reading, a single puzzle laid out over several lines is also accepted.
+    Line,
+    ///Simple Sudoku, a grid drawn with '|' and '-' between the blocks.
+    Ss,
+    ///SudoCue, one line per row with '.' for blanks.
+    Sdk,
+    ///SadMan Sudoku collections, one puzzle per line with '0' for blanks.
+    Sdm,
+    ///Jigsaw puzzles, a grid giving the block of each node followed by a grid of the givens.
+    ///Blocks are written with the same symbols as numbers.
+    Jigsaw,
+    ///Puzzles with extra constraints, a grid of the givens followed by a line for each constraint.
+    ///"diagonal", "windows", "anti-knight" and "anti-king" lines turn on those constraints.
+    ///Killer cages are written as e.g. "cage 15 r1c1 r1c2 r2c1" for a cage of three nodes adding up to 15.
+    ///Edge marks are written as e.g. "white r1c1 r1c2" or "greater r1c1 r2c1" with the larger node
+    ///first, and "negative black white" for marks that don't hold where they are missing.
+    Variant,
+}
+
+impl Format {
+    ///Picks the format from the file extension, anything unknown is read as a single line puzzle.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|x| x.to_str()).map(|x| x.to_ascii_lowercase()).as_deref() {
+            Some("ss")      => { Self::Ss }
+            Some("sdk")     => { Self::Sdk }
+            Some("sdm")     => { Self::Sdm }
+            Some("jigsaw")  => { Self::Jigsaw }
+            Some("variant") | Some("killer") | Some("kropki") => { Self::Variant }
+            _ => { Self::Line }
+        }
+    }
+
+    ///Reads every puzzle in the text. The grid formats can hold more than one puzzle when they are separated by blank lines.
+    pub fn read(&self, text: &str) -> Result<Vec<Puzzle>, FormatError> {
+        match self {
+            // one puzzle per line comes first, the text is only read as one puzzle over
+            // several lines when its lines aren't puzzles on their own. if neither works
+            // the error of the line that failed is the useful one.
+            Self::Line => {
+                match read_lines(text) {
+                    Ok(puzzles) => { Ok(puzzles) }
+                    Err(error) => { Puzzle::parse(text).map(|x| vec![x]).map_err(|_| error) }
+                }
+            }
+            Self::Sdm => { read_lines(text) }
+            Self::Ss | Self::Sdk => {
+                match read_grids(text) {
+                    Some(puzzles) => { Ok(puzzles) }
+                    None => { Ok(vec![Puzzle::parse(text)?]) }
+                }
+            }
+            Self::Jigsaw  => { read_jigsaw(text) }
+            Self::Variant => { read_variant(text) }
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Line    => { "line" }
+            Self::Ss      => { "ss" }
+            Self::Sdk     => { "sdk" }
+            Self::Sdm     => { "sdm" }
+            Self::Jigsaw  => { "jigsaw" }
+            Self::Variant => { "variant" }
+        }
+    }
+
+    ///True if the format can write every constraint of the puzzle. Jigsaw blocks only fit the
+    ///jigsaw format, the other constraints only the variant format.
+    pub fn holds(&self, puzzle: &Puzzle) -> bool {
+        let variant = puzzle.diagonal
+            || puzzle.windows
+            || !puzzle.chess.is_empty()
+            || !puzzle.cages.is_empty()
+            || !puzzle.edges.is_empty()
+            || !puzzle.negative.is_empty();
+
+        match self {
+            Self::Jigsaw  => { !variant }
+            Self::Variant => { puzzle.blocks.is_none() }
+            _ => { !variant && puzzle.blocks.is_none() }
+        }
+    }
+
+    ///Writes the puzzles, the grid formats put a blank line between them.
+    pub fn write(&self, puzzles: &[Puzzle]) -> String {
+        let written: Vec<String> = puzzles.iter().map(|x| self.write_one(x)).collect();
+
+        match self {
+            Self::Line | Self::Sdm => { written.concat() }
+            _ => { written.join("\n") }
+        }
+    }
+
+    fn write_one(&self, puzzle: &Puzzle) -> String {
+        match self {
+            Self::Line    => { write_rows(puzzle, '.', false) }
+            Self::Sdk     => { write_rows(puzzle, '.', true) }
+            Self::Sdm     => { write_rows(puzzle, '0', false) }
+            Self::Ss      => { write_ss(puzzle) }
+            Self::Jigsaw  => { write_jigsaw(puzzle) }
+            Self::Variant => { write_variant(puzzle) }
+        }
+    }
+}
+
+impl Puzzle {
+    ///Loads the first puzzle in the file, using the format that matches its extension.
+    pub fn load(path: &Path) -> Result<Self, FormatError> {
+        load_all(path)?.into_iter().next().ok_or(FormatError::BadCount(0))
+    }
+
+    ///Saves the puzzle using the format that matches the extension of the file.
+    pub fn save(&self, path: &Path) -> Result<(), FormatError> {
+        save_all(std::slice::from_ref(self), path)
+    }
+}
+
+pub fn load_all(path: &Path) -> Result<Vec<Puzzle>, FormatError> {
+    Format::from_path(path).read(&fs::read_to_string(path)?)
+}
+
+///Saves the puzzles using the format that matches the extension of the file, as long as it can hold
+///every constraint of the puzzles.
+pub fn save_all(puzzles: &[Puzzle], path: &Path) -> Result<(), FormatError> {
+    let format = Format::from_path(path);
+    if !puzzles.iter().all(|x| format.holds(x)) { return Err(FormatError::Unsupported(format.name())) }
+
+    fs::write(path, format.write(puzzles))?;
+    Ok(())
+}
+
+fn read_lines(text: &str) -> Result<Vec<Puzzle>, FormatError> {
+    text.lines()
+        .filter(|x| !x.trim().is_empty() && !x.trim_start().starts_with('#'))
+        .map(Puzzle::parse)
+        .collect()
+}
+
+///Reads each group of lines between blank lines as its own puzzle, groups without any cells are skipped.
+///Returns None if any group is not a whole puzzle.
+fn read_grids(text: &str) -> Option<Vec<Puzzle>> {
+    let mut puzzles = Vec::new();
+    let lines: Vec<&str> = text.lines().collect();
+
+    for grid in lines.split(|x| x.trim().is_empty()) {
+        match Puzzle::parse(&grid.join("\n")) {
+            Ok(puzzle) => { puzzles.push(puzzle) }
+            Err(FormatError::BadCount(0)) => {}
+            Err(_) => { return None }
+        }
+    }
+
+    if puzzles.is_empty() { None } else { Some(puzzles) }
+}
+
+///Reads each layout grid and the givens grid after it, both separated by blank lines.
+///A layout without givens after it is an empty puzzle.
+fn read_jigsaw(text: &str) -> Result<Vec<Puzzle>, FormatError> {
+    let lines: Vec<&str> = text.lines().filter(|x| !x.trim_start().starts_with('#')).collect();
+    let mut grids = lines.split(|x| x.trim().is_empty()).filter(|x| !x.is_empty()).peekable();
+    let mut puzzles = Vec::new();
+
+    while let Some(layout) = grids.next() {
+        let blocks = read_blocks(layout)?;
+        let size = blocks.size().x as usize;
+        Region::blocks(&blocks)?;
+
+        // a grid that is a valid layout as well starts the next puzzle. givens can't be mistaken
+        // for one, as the same number never sits beside itself.
+        let mut puzzle = match grids.next_if(|x| !is_layout(x)) {
+            Some(givens) => { Puzzle::parse(&givens.join("\n"))? }
+            None => { Puzzle::new(super::block_shape(size).ok_or(FormatError::BadCount(size * size))?) }
+        };
+
+        if puzzle.size() != size { return Err(FormatError::BadCount(puzzle.size() * puzzle.size())) }
+
+        puzzle.blocks = Some(blocks);
+        puzzles.push(puzzle);
+    }
+
+    if puzzles.is_empty() { return Err(FormatError::BadCount(0)) }
+
+    Ok(puzzles)
+}
+
+///True if the grid gives every node a block, with the blocks the right size and in one piece.
+fn is_layout(lines: &[&str]) -> bool {
+    read_blocks(lines).is_ok_and(|x| Region::blocks(&x).is_ok())
+}
+
+fn read_blocks(lines: &[&str]) -> Result<Vec2D<usize>, FormatError> {
+    let mut cells = Vec::new();
+
+    for c in lines.iter().flat_map(|x| x.chars()).filter(|x| !x.is_whitespace()) {
+        cells.push(char_num(c).ok_or(FormatError::BadChar(c))?);
+    }
+
+    let size = (1..=25).find(|x| x * x == cells.len()).ok_or(FormatError::BadCount(cells.len()))?;
+    let mut blocks = Vec2D::new(Coord::same(size as i32), 0);
+
+    for (i, block) in cells.into_iter().enumerate() {
+        *blocks.get_mut(Coord { x: (i % size) as i32, y: (i / size) as i32 }) = block;
+    }
+
+    Ok(blocks)
+}
+
+fn write_jigsaw(puzzle: &Puzzle) -> String {
+    let blocks = puzzle.blocks.clone().unwrap_or_else(|| Region::block_map(puzzle.sub_size));
+    let size = puzzle.size() as i32;
+    let mut out = String::new();
+
+    for y in 0..size {
+        for x in 0..size {
+            out.push(num_char(*blocks.get(Coord { x, y })));
+        }
+
+        out.push('\n');
+    }
+
+    out.push('\n');
+    out.push_str(&write_rows(puzzle, '.', true));
+    out
+}
+
+///Reads each givens grid along with the constraint lines after it, a grid after them starts the next puzzle.
+fn read_variant(text: &str) -> Result<Vec<Puzzle>, FormatError> {
+    let mut puzzles = Vec::new();
+    let mut grid = Vec::new();
+    let mut constraints = Vec::new();
+
+    for (i, line) in text.lines().enumerate().map(|(i, x)| (i + 1, x.trim())) {
+        if line.is_empty() || line.starts_with('#') { continue; }
+
+        if is_constraint(line) {
+            constraints.push((i, line));
+            continue;
+        }
+
+        if !constraints.is_empty() {
+            puzzles.push(read_constraints(&grid, &constraints)?);
+            grid.clear();
+            constraints.clear();
+        }
+
+        grid.push(line);
+    }
+
+    if !grid.is_empty() {
+        puzzles.push(read_constraints(&grid, &constraints)?);
+    }
+
+    if puzzles.is_empty() { return Err(FormatError::BadCount(0)) }
+
+    Ok(puzzles)
+}
+
+///True for the constraint lines that follow the givens.
+fn is_constraint(line: &str) -> bool {
+    let word = line.split_whitespace().next().unwrap_or_default();
+    ["cage", "negative", "diagonal", "windows"].contains(&word) || Chess::parse(word).is_some() || Mark::parse(word).is_some()
+}
+
+///Makes the puzzle from its givens and constraint lines, given with their line numbers. Windows need
+///square blocks, cages can't share nodes and need a sum their nodes can make, edges have to join neighbours.
+fn read_constraints(grid: &[&str], constraints: &[(usize, &str)]) -> Result<Puzzle, FormatError> {
+    let mut puzzle = Puzzle::parse(&grid.join("\n"))?;
+    let size = puzzle.size();
+
+    for (i, line) in constraints {
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        match words.as_slice() {
+            ["diagonal"] => { puzzle.diagonal = true }
+            ["windows"] => {
+                if puzzle.sub_size.x != puzzle.sub_size.y { return Err(FormatError::NotSquare(*i)) }
+
+                puzzle.windows = true;
+            }
+            [name] if Chess::parse(name).is_some() => {
+                puzzle.chess.extend(Chess::parse(name).filter(|x| !puzzle.chess.contains(x)));
+            }
+            ["negative", marks @ ..] => {
+                let marks: Vec<Mark> = marks.iter().map(|x| Mark::parse(x)).collect::<Option<_>>().ok_or(FormatError::BadLine(*i))?;
+                puzzle.negative.extend(marks);
+            }
+            ["cage", ..] => {
+                let cage = read_cage(line, size).filter(|x| x.is_valid(size) && !x.overlaps(&puzzle.cages)).ok_or(FormatError::BadLine(*i))?;
+                puzzle.cages.push(cage);
+            }
+            [mark, ..] if Mark::parse(mark).is_some() => {
+                let edge = read_edge(line, size).filter(|x| x.is_valid(size)).ok_or(FormatError::BadLine(*i))?;
+                puzzle.edges.push(edge);
+            }
+            // a known keyword with the wrong words after it, like "diagonal r1c1".
+            _ => { return Err(FormatError::BadLine(*i)) }
+        }
+    }
+
+    Ok(puzzle)
+}
+
+fn read_cage(line: &str, size: usize) -> Option<Cage> {
+    let mut words = line.split_whitespace();
+    if words.next() != Some("cage") { return None }
+
+    let sum = words.next()?.parse().ok()?;
+    let cells: Vec<Coord> = words.map(|x| read_cell(x, size)).collect::<Option<_>>()?;
+
+    Some(Cage::new(sum, cells))
+}
+
+fn read_edge(line: &str, size: usize) -> Option<Edge> {
+    let mut words = line.split_whitespace();
+    let mark = Mark::parse(words.next()?)?;
+    let a = read_cell(words.next()?, size)?;
+    let b = read_cell(words.next()?, size)?;
+
+    if words.next().is_some() { return None }
+
+    Some(Edge::new(mark, a, b))
+}
+
+///Reads a cell written like "r3c5", counting from 1.
+fn read_cell(word: &str, size: usize) -> Option<Coord> {
+    let (row, column) = word.strip_prefix('r')?.split_once('c')?;
+    let pos = Coord { x: column.parse::<i32>().ok()? - 1, y: row.parse::<i32>().ok()? - 1 };
+    let range = 0..size as i32;
+
+    (range.contains(&pos.x) && range.contains(&pos.y)).then_some(pos)
+}
+
+fn write_variant(puzzle: &Puzzle) -> String {
+    let mut out = write_rows(puzzle, '.', true);
+
+    if puzzle.diagonal {
+        out.push_str("diagonal\n");
+    }
+
+    if puzzle.windows {
+        out.push_str("windows\n");
+    }
+
+    for chess in &puzzle.chess {
+        out.push_str(&format!("{}\n", chess.name()));
+    }
+
+    for cage in &puzzle.cages {
+        let cells: Vec<String> = cage.cells.iter().map(|x| hint::name(*x)).collect();
+        out.push_str(&format!("cage {} {}\n", cage.sum, cells.join(" ")));
+    }
+
+    for edge in &puzzle.edges {
+        out.push_str(&format!("{} {} {}\n", edge.mark.name(), hint::name(edge.a), hint::name(edge.b)));
+    }
+
+    if !puzzle.negative.is_empty() {
+        let marks: Vec<&str> = puzzle.negative.iter().map(|x| x.name()).collect();
+        out.push_str(&format!("negative {}\n", marks.join(" ")));
+    }
+
+    out
+}
+
+fn cell_char(puzzle: &Puzzle, pos: Coord, blank: char) -> char {
+    puzzle.givens.get(pos).map_or(blank, num_char)
+}
+
+fn write_rows(puzzle: &Puzzle, blank: char, row_per_line: bool) -> String {
+    let size = puzzle.size() as i32;
+    let mut out = String::new();
+
+    for y in 0..size {
+        for x in 0..size {
+            out.push(cell_char(puzzle, Coord { x, y }, blank));
+        }
+
+        if row_per_line { out.push('\n'); }
+    }
+
+    if !row_per_line { out.push('\n'); }
+
+    out
+}
+
+fn write_ss(puzzle: &Puzzle) -> String {
+    let size = puzzle.size() as i32;
+    let block = puzzle.sub_size;
+    let width = (size + (size / block.x) - 1) as usize;
+
+    let mut divider = String::new();
+
+    for x in 0..size {
+        divider.push('-');
+
+        if x % block.x == block.x - 1 && x != size - 1 { divider.push('+'); }
+    }
+
+    let border = format!("*{}*\n", "-".repeat(width));
+
+    let mut out = border.clone();
+
+    for y in 0..size {
+        if y != 0 && y % block.y == 0 {
+            out.push_str(&format!("|{}|\n", divider));
+        }
+
+        out.push('|');
+
+        for x in 0..size {
+            out.push(cell_char(puzzle, Coord { x, y }, '.'));
+
+            if x % block.x == block.x - 1 { out.push('|'); }
+        }
+
+        out.push('\n');
+    }
+
+    out.push_str(&border);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::Solutions;
+
+    const PUZZLE: &str = "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+    #[test]
+    fn round_trip() {
+        let puzzle = Puzzle::parse(PUZZLE).unwrap();
+
+        for format in [Format::Line, Format::Ss, Format::Sdk, Format::Sdm] {
+            let text = format.write(&[puzzle.clone(), puzzle.clone()]);
+            let read = format.read(&text).unwrap();
+
+            assert_eq!(Format::Line.write(&read[..1]), format!("{}\n", PUZZLE));
+        }
+    }
+
+    #[test]
+    fn lines_before_grids() {
+        // sixteen small puzzles make 256 cells, as many as one 16x16 puzzle.
+        let text = "1234341221434321\n".repeat(16);
+        let read = Format::Line.read(&text).unwrap();
+
+        assert_eq!(read.len(), 16);
+        assert_eq!(read[0].size(), 4);
+
+        // rows of a single puzzle aren't puzzles on their own.
+        let grid: String = PUZZLE.as_bytes().chunks(9).map(|x| format!("{}\n", std::str::from_utf8(x).unwrap())).collect();
+        let read = Format::Line.read(&grid).unwrap();
+
+        assert_eq!(read.len(), 1);
+        assert_eq!(Format::Line.write(&read), format!("{}\n", PUZZLE));
+    }
+
+    #[test]
+    fn line_errors() {
+        // a short line is reported as itself, not as the count of the whole text.
+        let text = format!("{}\n{}\n", PUZZLE, &PUZZLE[1..]);
+        assert!(matches!(Format::Line.read(&text), Err(FormatError::BadCount(80))));
+
+        let text = format!("{}\n{}x\n", PUZZLE, &PUZZLE[1..]);
+        assert!(matches!(Format::Line.read(&text), Err(FormatError::BadChar('x'))));
+    }
+
+    #[test]
+    fn simple_sudoku() {
+        let text = "\
+            *-----------*\n\
+            |53.|.7.|...|\n\
+            |6..|195|...|\n\
+            |.98|...|.6.|\n\
+            |---+---+---|\n\
+            |8..|.6.|..3|\n\
+            |4..|8.3|..1|\n\
+            |7..|.2.|..6|\n\
+            |---+---+---|\n\
+            |.6.|...|28.|\n\
+            |...|419|..5|\n\
+            |...|.8.|.79|\n\
+            *-----------*\n";
+
+        assert_eq!(Format::Ss.write(&Format::Ss.read(text).unwrap()), text);
+    }
+
+    #[test]
+    fn jigsaw() {
+        let text = "\
+            # blocks\n\
+            1112\n\
+            1222\n\
+            3334\n\
+            3444\n\
+            \n\
+            1...\n\
+            ..2.\n\
+            .4..\n\
+            ...1\n";
+
+        let puzzles = Format::Jigsaw.read(text).unwrap();
+        assert_eq!(Format::Jigsaw.write(&puzzles), text.replace("# blocks\n", ""));
+
+        assert!(!Puzzle::new(Coord { x: 2, y: 2 }).to_state().is_jigsaw());
+
+        let mut state = puzzles[0].to_state();
+        assert!(state.is_jigsaw());
+        assert_eq!(*state.blocks().get(Coord { x: 3, y: 0 }), 1);
+        assert_eq!(state.count_solutions(2).0, 1);
+
+        // the top right node of block 1 is cut off from the rest.
+        let split = text.replace("1112", "1121");
+        assert!(matches!(Format::Jigsaw.read(&split), Err(FormatError::Blocks(_))));
+    }
+
+    #[test]
+    fn jigsaw_without_givens() {
+        let empty = "1122\n1122\n3344\n3344\n";
+        let solved = "1234\n3412\n2143\n4321\n";
+        let text = format!("{}\n1112\n1222\n3334\n3444\n\n1...\n..2.\n.4..\n...1\n", empty);
+
+        // the first layout has no givens, the second grid is the next layout rather than its givens.
+        let puzzles = Format::Jigsaw.read(&text).unwrap();
+        assert_eq!(puzzles.len(), 2);
+        assert_eq!(puzzles[0].givens.vec().iter().flatten().count(), 0);
+        assert_eq!(puzzles[1].givens.vec().iter().flatten().count(), 4);
+
+        // a grid with every number given is read as givens.
+        let puzzles = Format::Jigsaw.read(&format!("{}\n{}", empty, solved)).unwrap();
+        assert_eq!(puzzles.len(), 1);
+        assert_eq!(puzzles[0].givens.vec().iter().flatten().count(), 16);
+    }
+
+    #[test]
+    fn killer() {
+        let text = "\
+            .........\n.........\n.........\n.........\n.........\n.........\n.........\n.........\n.........\n\
+            cage 21 r1c1 r2c1 r1c2 r2c2\ncage 13 r1c3 r1c4 r2c4 r2c3\ncage 15 r1c5 r1c6\ncage 10 r1c7 r1c8\n\
+            cage 10 r1c9 r2c9\ncage 19 r2c5 r3c5 r4c5\ncage 8 r2c6 r2c7\ncage 10 r2c8 r3c8\n\
+            cage 22 r3c1 r3c2 r4c1 r5c1\ncage 11 r3c3 r3c4\ncage 7 r3c6 r3c7\ncage 10 r3c9 r4c9\n\
+            cage 16 r4c2 r5c2 r4c3\ncage 21 r4c4 r5c4 r5c3\ncage 5 r4c6 r4c7\ncage 11 r4c8 r5c8\n\
+            cage 12 r5c5 r5c6 r6c6\ncage 17 r5c7 r6c7 r7c7\ncage 12 r5c9 r6c9 r6c8\ncage 16 r6c1 r7c1\n\
+            cage 18 r6c2 r7c2 r6c3 r8c2\ncage 14 r6c4 r7c4\ncage 5 r6c5 r7c5\ncage 17 r7c3 r8c3 r9c3 r8c4\n\
+            cage 17 r7c6 r8c6 r8c5\ncage 12 r7c8 r7c9\ncage 9 r8c1 r9c1 r9c2\ncage 17 r8c7 r8c8 r9c7 r9c8\n\
+            cage 14 r8c9 r9c9\ncage 10 r9c4 r9c5\ncage 6 r9c6\n";
+
+        let puzzles = Format::Variant.read(text).unwrap();
+        assert_eq!(puzzles[0].cages.len(), 31);
+        assert_eq!(Format::Variant.write(&puzzles), text);
+
+        // no givens, the cages alone make the solution unique.
+        let mut state = puzzles[0].to_state();
+        assert!(state.check_if_possible());
+        assert_eq!(state.finished(), 0);
+
+        match state.solutions() {
+            Solutions::Unique(grid) => {
+                let row: String = (0..9).map(|x| num_char(*grid.get(Coord { x, y: 0 }))).collect();
+                assert_eq!(row, "534678912");
+            }
+            _ => { panic!("the killer should have one solution") }
+        }
+
+        // r1c2 is already in the first cage.
+        let overlap = text.replace("cage 15 r1c5 r1c6", "cage 15 r1c5 r1c2");
+        assert!(matches!(Format::Variant.read(&overlap), Err(FormatError::BadLine(12))));
+
+        // two nodes can't add up to 1.
+        let impossible = text.replace("cage 15 r1c5 r1c6", "cage 1 r1c5 r1c6");
+        assert!(matches!(Format::Variant.read(&impossible), Err(FormatError::BadLine(12))));
+    }
+
+    #[test]
+    fn edges() {
+        let text = "1...\n....\n....\n....\nwhite r1c1 r1c2\ngreater r2c1 r1c1\nx r3c3 r3c4\nnegative black white\n";
+
+        let puzzles = Format::Variant.read(text).unwrap();
+        assert_eq!(puzzles[0].edges.len(), 3);
+        assert_eq!(puzzles[0].edges[1], Edge::new(Mark::Greater, Coord { x: 0, y: 1 }, Coord { x: 0, y: 0 }));
+        assert_eq!(puzzles[0].negative, vec![Mark::Black, Mark::White]);
+        assert_eq!(Format::Variant.write(&puzzles), text);
+        assert_eq!(Format::from_path(Path::new("dots.kropki")), Format::Variant);
+
+        let state = puzzles[0].to_state();
+        assert_eq!(state.edges().len(), 3);
+        assert!(!state.has_conflicts());
+
+        // edges have to join neighbours.
+        let apart = text.replace("x r3c3 r3c4", "x r3c3 r4c4");
+        assert!(matches!(Format::Variant.read(&apart), Err(FormatError::BadLine(7))));
+
+        // the words after a keyword that isn't a mark are never read as an edge.
+        for line in ["diagonal r1c1 r1c2", "anti-king r1c1 r1c2", "windows 2"] {
+            let extra = text.replace("x r3c3 r3c4", line);
+            assert!(matches!(Format::Variant.read(&extra), Err(FormatError::BadLine(7))));
+        }
+    }
+
+    #[test]
+    fn variants() {
+        let mut puzzle = Puzzle::parse(PUZZLE).unwrap();
+        puzzle.diagonal = true;
+        puzzle.windows = true;
+        puzzle.chess = vec![Chess::Knight, Chess::King];
+
+        let text = Format::Variant.write(std::slice::from_ref(&puzzle));
+        assert!(text.ends_with("diagonal\nwindows\nanti-knight\nanti-king\n"));
+
+        let read = Format::Variant.read(&text).unwrap();
+        assert!(read[0] == puzzle);
+
+        // the other formats would lose the constraints.
+        assert!(Format::Variant.holds(&puzzle));
+        assert!(!Format::Line.holds(&puzzle));
+        assert!(!Format::Jigsaw.holds(&puzzle));
+
+        let path = std::env::temp_dir().join("sudoku-variants-test.sdk");
+        assert!(matches!(puzzle.save(&path), Err(FormatError::Unsupported("sdk"))));
+
+        // windows need square blocks.
+        let wide = format!("{}windows\n", Format::Variant.write(&[Puzzle::new(Coord { x: 3, y: 2 })]));
+        assert!(matches!(Format::Variant.read(&wide), Err(FormatError::NotSquare(7))));
+    }
+
+    #[test]
+    fn large() {
+        let mut puzzle = Puzzle::new(Coord { x: 4, y: 4 });
+        *puzzle.givens.get_mut(Coord { x: 3, y: 0 }) = Some(15);
+        *puzzle.givens.get_mut(Coord { x: 15, y: 15 }) = Some(9);
+
+        let text = Format::Sdm.write(&[puzzle]);
+        assert_eq!(&text[..4], "000G");
+
+        let read = Format::Sdm.read(&text).unwrap();
+        assert_eq!(read[0].sub_size, Coord { x: 4, y: 4 });
+        assert_eq!(*read[0].givens.get(Coord { x: 15, y: 15 }), Some(9));
+
+        let mut puzzle = Puzzle::new(Coord { x: 5, y: 5 });
+        *puzzle.givens.get_mut(Coord { x: 0, y: 0 }) = Some(24);
+
+        let read = Format::Sdk.read(&Format::Sdk.write(&[puzzle])).unwrap();
+        assert_eq!(*read[0].givens.get(Coord { x: 0, y: 0 }), Some(24));
+    }
+}
@@ -0,0 +1,14 @@
+mod puzzle;
+pub use puzzle::{Puzzle, block_shape};
+
+mod format;
+pub use format::{Format, load_all, save_all};
+
+mod session;
+pub use session::{write_session, read_session, save_session, load_session};
+
+mod multi;
+pub use multi::{write_multi, read_multi, save_multi, load_multi};
+
+mod error;
+pub use error::FormatError;
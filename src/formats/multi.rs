@@ -0,0 +1,188 @@
+use std::fs;
+use std::path::Path;
+
+use crate::prelude::*;
+use crate::state::{MultiState, Shape};
+use crate::graphics::{num_char, char_num};
+
+use super::FormatError;
+
+///Writes a multi-grid puzzle as its shape and block size, e.g. "samurai 3x3", followed by one line
+///for each row of the whole puzzle with '.' for blanks and spaces in the gaps between grids.
+///Only the givens go in those rows, the numbers entered since follow in the same way after an "entries" line.
+pub fn write_multi(multi: &MultiState) -> String {
+    let sub_size = multi.sub_size();
+    let mut out = format!("{} {}x{}\n", multi.shape().name(), sub_size.x, sub_size.y);
+
+    write_rows(&mut out, multi, |pos| multi.is_given(pos));
+
+    if multi.all_iter().any(|pos| multi.node(pos).is_some_and(|x| x.is_found()) && !multi.is_given(pos)) {
+        out.push_str("entries\n");
+        write_rows(&mut out, multi, |pos| !multi.is_given(pos));
+    }
+
+    out
+}
+
+///Writes every row of the whole puzzle, leaving out the numbers that aren't kept.
+fn write_rows(out: &mut String, multi: &MultiState, keep: impl Fn(Coord) -> bool) {
+    let size = multi.size();
+
+    for y in 0..size.y {
+        let row: String = (0..size.x)
+            .map(|x| {
+                let pos = Coord { x, y };
+
+                match multi.node(pos) {
+                    Some(node) => { node.get_num().filter(|_| keep(pos)).map_or('.', num_char) }
+                    None => { ' ' }
+                }
+            })
+            .collect();
+
+        out.push_str(row.trim_end());
+        out.push('\n');
+    }
+}
+
+///Reads a puzzle written by `write_multi`. The block size can be left out for 3x3 blocks,
+///and '0' is also read as a blank. The puzzle is left at guess level 1, so numbers set
+///after reading are kept apart from the givens.
+pub fn read_multi(text: &str) -> Result<MultiState, FormatError> {
+    let mut lines = text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim_end()))
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'));
+
+    let (i, header) = lines.next().ok_or(FormatError::BadCount(0))?;
+    let mut words = header.split_whitespace();
+
+    let shape = words.next().and_then(Shape::parse).ok_or(FormatError::BadLine(i))?;
+    let sub_size = match words.next() {
+        Some(word) => { read_block(word).ok_or(FormatError::BadLine(i))? }
+        None => { Coord { x: 3, y: 3 } }
+    };
+
+    let mut multi = MultiState::new(shape, sub_size);
+    let size = multi.size();
+    let mut rows = 0;
+    let mut entries = false;
+
+    for (i, line) in lines {
+        if rows == size.y {
+            if line.trim() != "entries" || entries { return Err(FormatError::BadLine(i)) }
+
+            entries = true;
+            rows = 0;
+            multi.inc_guess();
+            continue;
+        }
+
+        for (x, c) in line.chars().enumerate() {
+            let pos = Coord { x: x as i32, y: rows };
+
+            if !multi.contains(pos) {
+                if c == ' ' || c == '.' { continue; }
+                return Err(FormatError::BadLine(i))
+            }
+
+            match c {
+                '.' | '0' => {}
+                _ => {
+                    let num = char_num(c).ok_or(FormatError::BadChar(c))?;
+                    if num >= multi.grids()[0].size() { return Err(FormatError::OutOfRange(c)) }
+
+                    multi.set(pos, num);
+                }
+            }
+        }
+
+        rows += 1;
+    }
+
+    if rows != size.y { return Err(FormatError::BadCount(rows as usize)) }
+    if !entries { multi.inc_guess(); }
+
+    Ok(multi)
+}
+
+///Reads a block size written as WxH. Blocks one node wide would leave the grids nothing to share.
+fn read_block(word: &str) -> Option<Coord> {
+    let (x, y) = word.split_once('x')?;
+    let block = Coord { x: x.parse().ok()?, y: y.parse().ok()? };
+
+    (block.x > 1 && block.y > 1 && block.x * block.y <= 25).then_some(block)
+}
+
+pub fn save_multi(multi: &MultiState, path: &Path) -> Result<(), FormatError> {
+    fs::write(path, write_multi(multi))?;
+    Ok(())
+}
+
+pub fn load_multi(path: &Path) -> Result<MultiState, FormatError> {
+    read_multi(&fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut multi = MultiState::new(Shape::Samurai, Coord { x: 3, y: 3 });
+        multi.set(Coord { x: 0, y: 0 }, 4);
+        multi.set(Coord { x: 7, y: 7 }, 2);
+        multi.set(Coord { x: 20, y: 20 }, 8);
+
+        let text = write_multi(&multi);
+        let mut lines = text.lines();
+
+        assert_eq!(lines.next(), Some("samurai 3x3"));
+        assert_eq!(lines.next(), Some("5........   ........."));
+
+        let read = read_multi(&text).unwrap();
+
+        assert_eq!(write_multi(&read), text);
+        // the shared node is set in both grids holding it.
+        assert_eq!(read.grids()[2].nodes().get(Coord { x: 1, y: 1 }).get_num(), Some(2));
+    }
+
+    #[test]
+    fn entries_kept_apart() {
+        let mut multi = MultiState::new(Shape::Twin, Coord { x: 3, y: 3 });
+        multi.set(Coord { x: 0, y: 0 }, 4);
+        multi.inc_guess();
+        multi.set(Coord { x: 1, y: 0 }, 2);
+
+        let text = write_multi(&multi);
+        let lines: Vec<_> = text.lines().collect();
+
+        assert_eq!(lines[1], "5........");
+        assert_eq!(lines[16], "entries");
+        assert_eq!(lines[17], ".3.......");
+
+        let mut read = read_multi(&text).unwrap();
+
+        assert_eq!(write_multi(&read), text);
+        assert!(read.is_given(Coord { x: 0, y: 0 }));
+        assert!(!read.is_given(Coord { x: 1, y: 0 }));
+
+        // without the entries only the givens are left.
+        read.dec_guess();
+        assert_eq!(read.finished(), 1);
+        assert!(!write_multi(&read).contains("entries"));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(read_multi("triple\n"), Err(FormatError::BadLine(1))));
+        assert!(matches!(read_multi("twin\n.........\n"), Err(FormatError::BadCount(1))));
+        assert!(matches!(read_multi("twin 1x4\n"), Err(FormatError::BadLine(1))));
+        assert!(matches!(read_multi(&format!("twin\n{}more\n", ".\n".repeat(15))), Err(FormatError::BadLine(17))));
+
+        // a number in the gap between the two grids.
+        let mut text = String::from("twin\n");
+        text.push_str(&".........5\n".repeat(15));
+        assert!(matches!(read_multi(&text), Err(FormatError::BadLine(2))));
+    }
+}
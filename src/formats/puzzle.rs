@@ -0,0 +1,241 @@
+use crate::prelude::*;
+use crate::state::{State, Node, Cage, Chess, Edge, Mark};
+use crate::graphics::char_num;
+
+use super::FormatError;
+
+///The given numbers of a puzzle along with the shape of its blocks.
+#[derive(Clone, PartialEq)]
+pub struct Puzzle {
+    pub sub_size: Coord,
+    pub givens:   Vec2D<Option<usize>>,
+    ///Both diagonals need every number once as well.
+    pub diagonal: bool,
+    ///The windows of a windoku need every number once as well, only for square blocks.
+    pub windows:  bool,
+    ///Which block each node is in for jigsaw puzzles, None for the usual blocks.
+    pub blocks:   Option<Vec2D<usize>>,
+    ///Killer cages, empty for other puzzles.
+    pub cages:    Vec<Cage>,
+    ///Chess moves that can't join two of the same number.
+    pub chess:    Vec<Chess>,
+    ///Kropki dots, X, V and greater-than signs between neighbouring nodes.
+    pub edges:    Vec<Edge>,
+    ///Marks that don't hold between neighbours without one.
+    pub negative: Vec<Mark>,
+}
+
+impl Puzzle {
+    pub fn new(sub_size: Coord) -> Self {
+        let size = sub_size.x * sub_size.y;
+
+        Self {
+            sub_size,
+            givens:   Vec2D::new(Coord::same(size), None),
+            diagonal: false,
+            windows:  false,
+            blocks:   None,
+            cages:    Vec::new(),
+            chess:    Vec::new(),
+            edges:    Vec::new(),
+            negative: Vec::new(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        (self.sub_size.x * self.sub_size.y) as usize
+    }
+
+    ///Reads a puzzle from text, the block shape is inferred from the number of cells found.
+    ///Whitespace, grid separators and lines starting with '#' or '[' are ignored, and '.', '0' or '_' mark a blank.
+    pub fn parse(text: &str) -> Result<Self, FormatError> {
+        let mut cells = Vec::new();
+
+        for line in text.lines() {
+            let line = line.trim_start();
+            if line.starts_with('#') || line.starts_with('[') { continue; }
+
+            for c in line.chars() {
+                match c {
+                    '.' | '0' | '_' => { cells.push(None) }
+                    '|' | '-' | '+' | '*' => {}
+                    _ if c.is_whitespace() => {}
+                    _ => {
+                        match char_num(c) {
+                            Some(num) => { cells.push(Some((num, c))) }
+                            None => { return Err(FormatError::BadChar(c)) }
+                        }
+                    }
+                }
+            }
+        }
+
+        Self::from_cells(&cells)
+    }
+
+    fn from_cells(cells: &[Option<(usize, char)>]) -> Result<Self, FormatError> {
+        let size = (1..=25).find(|x| x * x == cells.len());
+        let sub_size = size.and_then(block_shape).ok_or(FormatError::BadCount(cells.len()))?;
+
+        let mut puzzle = Self::new(sub_size);
+        let size = puzzle.size();
+
+        for (i, cell) in cells.iter().enumerate() {
+            if let Some((num, c)) = cell {
+                if *num >= size { return Err(FormatError::OutOfRange(*c)) }
+
+                let pos = Coord { x: (i % size) as i32, y: (i / size) as i32 };
+                *puzzle.givens.get_mut(pos) = Some(*num);
+            }
+        }
+
+        Ok(puzzle)
+    }
+
+    ///An empty puzzle with the block shape and every constraint of the state.
+    fn constraints(state: &State) -> Self {
+        let mut puzzle = Self::new(state.sub_size());
+        puzzle.diagonal = state.is_diagonal();
+        puzzle.windows = state.is_windoku();
+        puzzle.blocks = state.is_jigsaw().then(|| state.blocks());
+        puzzle.cages = state.cages().to_vec();
+        puzzle.chess = state.chess().to_vec();
+        puzzle.edges = state.edges().to_vec();
+        puzzle.negative = state.negative().to_vec();
+        puzzle
+    }
+
+    ///Takes the nodes solved at guess level 0 as the givens.
+    pub fn from_state(state: &State) -> Self {
+        let mut puzzle = Self::constraints(state);
+
+        for pos in CoordIter::new(Coord { x: 0, y: 0 }, state.nodes().size()) {
+            if let Node::Found(data) = state.nodes().get(pos) {
+                if data.guess_level == 0 {
+                    *puzzle.givens.get_mut(pos) = Some(data.num);
+                }
+            }
+        }
+
+        puzzle
+    }
+
+    ///Takes every solved node, whatever its guess level.
+    pub fn grid(state: &State) -> Self {
+        let mut puzzle = Self::constraints(state);
+
+        for pos in CoordIter::new(Coord { x: 0, y: 0 }, state.nodes().size()) {
+            *puzzle.givens.get_mut(pos) = state.nodes().get(pos).get_num();
+        }
+
+        puzzle
+    }
+
+    ///Creates a new state with every given solved at guess level 0.
+    pub fn to_state(&self) -> State {
+        let mut state = State::new(self.sub_size);
+
+        if let Some(blocks) = &self.blocks {
+            state.set_blocks(blocks).expect("jigsaw blocks are checked when they are read");
+        }
+
+        if self.diagonal {
+            state.add_diagonals();
+        }
+
+        if self.windows {
+            state.add_windows();
+        }
+
+        for cage in &self.cages {
+            state.add_cage(cage.clone());
+        }
+
+        for chess in &self.chess {
+            state.add_chess(*chess);
+        }
+
+        for edge in &self.edges {
+            state.add_edge(*edge);
+        }
+
+        state.set_negative(&self.negative);
+
+        for pos in CoordIter::new(Coord { x: 0, y: 0 }, self.givens.size()) {
+            if let Some(num) = self.givens.get(pos) {
+                state.set(pos, *num);
+            }
+        }
+
+        state
+    }
+}
+
+///Picks the block shape for a puzzle with the given number of symbols, keeping the blocks as close to square as possible.
+///Blocks are wider than they are tall when they can't be square.
+pub fn block_shape(size: usize) -> Option<Coord> {
+    if size == 0 || size > 25 { return None }
+
+    let height = (1..=size)
+        .take_while(|x| x * x <= size)
+        .filter(|x| size.is_multiple_of(*x))
+        .last()
+        .unwrap();
+
+    Some(Coord { x: (size / height) as i32, y: height as i32 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let text = "# a comment\n[header]\n1 2 | . .\n_ 0 | 4 .\n----+----\n. . | . 1\n. . | 2 .\n";
+        let puzzle = Puzzle::parse(text).unwrap();
+
+        assert_eq!(puzzle.sub_size, Coord { x: 2, y: 2 });
+        assert_eq!(puzzle.givens.get(Coord { x: 1, y: 0 }), &Some(1));
+        assert_eq!(puzzle.givens.get(Coord { x: 2, y: 1 }), &Some(3));
+        assert_eq!(puzzle.givens.vec().iter().filter(|x| x.is_some()).count(), 5);
+
+        let six = Puzzle::parse(&"1".repeat(36)).unwrap();
+        assert_eq!(six.sub_size, Coord { x: 3, y: 2 });
+
+        assert!(matches!(Puzzle::parse("1234x..........."), Err(FormatError::BadChar('x'))));
+        assert!(matches!(Puzzle::parse("5..............."), Err(FormatError::OutOfRange('5'))));
+        assert!(matches!(Puzzle::parse(".............."), Err(FormatError::BadCount(14))));
+    }
+
+    #[test]
+    fn constraints_kept() {
+        let mut puzzle = Puzzle::parse("1...............").unwrap();
+        puzzle.diagonal = true;
+        puzzle.chess.push(Chess::King);
+        puzzle.cages.push(Cage::new(7, [Coord { x: 2, y: 3 }, Coord { x: 3, y: 3 }]));
+        puzzle.edges.push(Edge::new(Mark::White, Coord { x: 0, y: 1 }, Coord { x: 0, y: 2 }));
+        puzzle.negative.push(Mark::White);
+
+        let mut state = puzzle.to_state();
+        assert!(Puzzle::from_state(&state) == puzzle);
+
+        state.inc_guess();
+        state.set(Coord { x: 3, y: 0 }, 2);
+        let grid = Puzzle::grid(&state);
+
+        assert_eq!(grid.givens.get(Coord { x: 3, y: 0 }), &Some(2));
+        assert!(Puzzle { givens: puzzle.givens.clone(), ..grid } == puzzle);
+    }
+
+    #[test]
+    fn block_shapes() {
+        let shapes = [(1, (1, 1)), (4, (2, 2)), (6, (3, 2)), (7, (7, 1)), (9, (3, 3)), (12, (4, 3)), (16, (4, 4)), (25, (5, 5))];
+
+        for (size, (x, y)) in shapes {
+            assert_eq!(block_shape(size), Some(Coord { x, y }));
+        }
+
+        assert_eq!(block_shape(0), None);
+        assert_eq!(block_shape(26), None);
+    }
+}
@@ -0,0 +1,365 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::prelude::*;
+use crate::state::{State, Node, Cage, Chess, Edge, Mark};
+use crate::graphics::{num_char, char_num};
+
+use super::FormatError;
+
+const HEADER: &str = "sudoku session";
+
+///Writes the whole state of a puzzle, including the guess level of every solved node and elimination,
+///the pencil marks and the guess stack.
+pub fn write_session(state: &State) -> String {
+    let sub_size = state.sub_size();
+
+    let mut out = format!(
+        "{}\nblock {} {}\nlevel {}\n",
+        HEADER, sub_size.x, sub_size.y, state.guess_level()
+    );
+
+    if state.is_diagonal() {
+        out.push_str("diagonal\n");
+    }
+
+    if state.is_windoku() {
+        out.push_str("windows\n");
+    }
+
+    for chess in state.chess() {
+        out.push_str(&format!("{}\n", chess.name()));
+    }
+
+    if state.is_jigsaw() {
+        let blocks = state.blocks();
+
+        for y in 0..state.size() as i32 {
+            let row: String = (0..state.size() as i32).map(|x| num_char(*blocks.get(Coord { x, y }))).collect();
+            out.push_str(&format!("blocks {}\n", row));
+        }
+    }
+
+    for cage in state.cages() {
+        let cells: Vec<String> = cage.cells.iter().map(|pos| format!("{} {}", pos.y, pos.x)).collect();
+        out.push_str(&format!("cage {} {}\n", cage.sum, cells.join(" ")));
+    }
+
+    for edge in state.edges() {
+        out.push_str(&format!("edge {} {} {} {} {}\n", edge.mark.name(), edge.a.y, edge.a.x, edge.b.y, edge.b.x));
+    }
+
+    if !state.negative().is_empty() {
+        let marks: Vec<&str> = state.negative().iter().map(|x| x.name()).collect();
+        out.push_str(&format!("negative {}\n", marks.join(" ")));
+    }
+
+    for pos in CoordIter::new(Coord { x: 0, y: 0 }, state.nodes().size()) {
+        if let Node::Found(data) = state.nodes().get(pos) {
+            out.push_str(&format!("cell {} {} {} {}\n", pos.y, pos.x, num_char(data.num), data.guess_level));
+        }
+    }
+
+    for (pos, num, level) in state.eliminations() {
+        out.push_str(&format!("eliminated {} {} {} {}\n", pos.y, pos.x, num_char(num), level));
+    }
+
+    for pos in CoordIter::new(Coord { x: 0, y: 0 }, state.nodes().size()) {
+        for num in state.marks(pos).iter() {
+            out.push_str(&format!("mark {} {} {}\n", pos.y, pos.x, num_char(num)));
+        }
+    }
+
+    for pos in state.guesses() {
+        out.push_str(&format!("guess {} {}\n", pos.y, pos.x));
+    }
+
+    out
+}
+
+pub fn read_session(text: &str) -> Result<State, FormatError> {
+    let mut lines = text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+    match lines.next() {
+        Some((_, HEADER)) => {}
+        Some((i, _)) => { return Err(FormatError::BadLine(i)) }
+        None => { return Err(FormatError::BadLine(1)) }
+    }
+
+    let mut sub_size = None;
+    let mut level = 0;
+    let mut diagonal = false;
+    let mut windows = false;
+    let mut chess = Vec::new();
+    let mut blocks = Vec::new();
+    let mut cages = Vec::new();
+    let mut edges = Vec::new();
+    let mut negative = Vec::new();
+    let mut found: Vec<(Coord, usize, usize)> = Vec::new();
+    let mut eliminations = Vec::new();
+    let mut marks = Vec::new();
+    let mut guesses = Vec::new();
+    // the highest guess level of a node or elimination and its line, none can be above the session's level.
+    let mut highest = (0, 0);
+
+    for (i, line) in lines {
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        match (words.as_slice(), sub_size) {
+            (["block", x, y], None) => {
+                let new = Coord { x: number(x, i)?, y: number(y, i)? };
+                let size = new.x * new.y;
+
+                if new.x < 1 || new.y < 1 || size > 25 { return Err(FormatError::BadLine(i)) }
+
+                sub_size = Some(new);
+            }
+            (["level", x], _) => {
+                level = number(x, i)?;
+            }
+            (["diagonal"], _) => {
+                diagonal = true;
+            }
+            (["windows"], Some(sub_size)) => {
+                if sub_size.x != sub_size.y { return Err(FormatError::NotSquare(i)) }
+
+                windows = true;
+            }
+            ([name], _) if Chess::parse(name).is_some() => {
+                chess.extend(Chess::parse(name));
+            }
+            (["blocks", row], Some(sub_size)) => {
+                let row: Vec<usize> = row.chars().map(|x| symbol(&x.to_string(), sub_size, i)).collect::<Result<_, _>>()?;
+
+                if row.len() != (sub_size.x * sub_size.y) as usize { return Err(FormatError::BadLine(i)) }
+
+                blocks.push((i, row));
+            }
+            (["cage", sum, cells @ ..], Some(sub_size)) => {
+                if cells.is_empty() || cells.len() % 2 != 0 { return Err(FormatError::BadLine(i)) }
+
+                let cells = cells.chunks(2).map(|x| position(x[0], x[1], sub_size, i)).collect::<Result<Vec<_>, _>>()?;
+                let cage = Cage::new(number(sum, i)?, cells);
+
+                if !cage.is_valid((sub_size.x * sub_size.y) as usize) || cage.overlaps(&cages) { return Err(FormatError::BadLine(i)) }
+
+                cages.push(cage);
+            }
+            (["edge", mark, ay, ax, by, bx], Some(sub_size)) => {
+                let mark = Mark::parse(mark).ok_or(FormatError::BadLine(i))?;
+                let edge = Edge::new(mark, position(ay, ax, sub_size, i)?, position(by, bx, sub_size, i)?);
+
+                if !edge.is_valid((sub_size.x * sub_size.y) as usize) { return Err(FormatError::BadLine(i)) }
+
+                edges.push(edge);
+            }
+            (["negative", marks @ ..], _) => {
+                let marks: Vec<Mark> = marks.iter().map(|x| Mark::parse(x)).collect::<Option<_>>().ok_or(FormatError::BadLine(i))?;
+                negative.extend(marks);
+            }
+            (["cell", y, x, num, cell_level], Some(sub_size)) => {
+                let pos = position(y, x, sub_size, i)?;
+                let num = symbol(num, sub_size, i)?;
+                let cell_level = number(cell_level, i)?;
+
+                if found.iter().any(|x| x.0 == pos) { return Err(FormatError::BadLine(i)) }
+
+                highest = highest.max((cell_level, i));
+                found.push((pos, num, cell_level));
+            }
+            (["eliminated", y, x, num, cell_level], Some(sub_size)) => {
+                let pos = position(y, x, sub_size, i)?;
+                let num = symbol(num, sub_size, i)?;
+                let cell_level = number(cell_level, i)?;
+
+                highest = highest.max((cell_level, i));
+                eliminations.push((pos, num, cell_level));
+            }
+            (["mark", y, x, num], Some(sub_size)) => {
+                marks.push((position(y, x, sub_size, i)?, symbol(num, sub_size, i)?));
+            }
+            (["guess", y, x], Some(sub_size)) => {
+                let pos = position(y, x, sub_size, i)?;
+
+                if !found.iter().any(|x| x.0 == pos) { return Err(FormatError::BadLine(i)) }
+
+                guesses.push(pos);
+            }
+            _ => { return Err(FormatError::BadLine(i)) }
+        }
+    }
+
+    if highest.0 > level { return Err(FormatError::BadLine(highest.1)) }
+
+    match sub_size {
+        Some(sub_size) => {
+            let mut state = State::restore(sub_size, level, &found, &eliminations, guesses);
+
+            if !blocks.is_empty() {
+                let size = (sub_size.x * sub_size.y) as usize;
+                if blocks.len() != size { return Err(FormatError::BadLine(blocks[0].0)) }
+
+                let mut map = Vec2D::new(Coord::same(size as i32), 0);
+
+                for (y, (_, row)) in blocks.iter().enumerate() {
+                    for (x, block) in row.iter().enumerate() {
+                        *map.get_mut(Coord { x: x as i32, y: y as i32 }) = *block;
+                    }
+                }
+
+                state.set_blocks(&map)?;
+            }
+
+            if diagonal {
+                state.add_diagonals();
+            }
+
+            if windows {
+                state.add_windows();
+            }
+
+            for cage in cages {
+                state.add_cage(cage);
+            }
+
+            for chess in chess {
+                state.add_chess(chess);
+            }
+
+            for edge in edges {
+                state.add_edge(edge);
+            }
+
+            state.set_negative(&negative);
+
+            for (pos, num) in marks {
+                state.add_mark(pos, num);
+            }
+
+            Ok(state)
+        }
+        None => { Err(FormatError::BadLine(text.lines().count())) }
+    }
+}
+
+pub fn save_session(state: &State, path: &Path) -> Result<(), FormatError> {
+    fs::write(path, write_session(state))?;
+    Ok(())
+}
+
+pub fn load_session(path: &Path) -> Result<State, FormatError> {
+    read_session(&fs::read_to_string(path)?)
+}
+
+fn number<T: FromStr>(word: &str, line: usize) -> Result<T, FormatError> {
+    word.parse().map_err(|_| FormatError::BadLine(line))
+}
+
+fn position(y: &str, x: &str, sub_size: Coord, line: usize) -> Result<Coord, FormatError> {
+    let size = sub_size.x * sub_size.y;
+    let pos = Coord { x: number(x, line)?, y: number(y, line)? };
+
+    if pos.x < 0 || pos.y < 0 || pos.x >= size || pos.y >= size {
+        return Err(FormatError::BadLine(line))
+    }
+
+    Ok(pos)
+}
+
+fn symbol(word: &str, sub_size: Coord, line: usize) -> Result<usize, FormatError> {
+    let mut chars = word.chars();
+
+    match (chars.next().and_then(char_num), chars.next()) {
+        (Some(num), None) if num < (sub_size.x * sub_size.y) as usize => { Ok(num) }
+        _ => { Err(FormatError::BadLine(line)) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut state = State::new(Coord { x: 3, y: 3 });
+        state.add_diagonals();
+        state.add_chess(Chess::King);
+        state.add_cage(Cage::new(3, vec![Coord { x: 0, y: 8 }, Coord { x: 1, y: 8 }]));
+        state.add_edge(Edge::new(Mark::White, Coord { x: 4, y: 4 }, Coord { x: 5, y: 4 }));
+        state.set_negative(&[Mark::White]);
+
+        state.set(Coord { x: 0, y: 0 }, 4);
+        state.inc_guess();
+        state.set(Coord { x: 2, y: 1 }, 7);
+        state.eliminate(Coord { x: 8, y: 8 }, 3);
+        state.toggle_mark(Coord { x: 6, y: 6 }, 1);
+
+        let text = write_session(&state);
+        let read = read_session(&text).unwrap();
+
+        assert_eq!(write_session(&read), text);
+        assert_eq!(read.guess_level(), 1);
+        assert_eq!(read.nodes().vec(), state.nodes().vec());
+        assert!(read.marks(Coord { x: 6, y: 6 }).has(1));
+    }
+
+    #[test]
+    fn after_checking() {
+        let mut state = State::new(Coord { x: 2, y: 2 });
+        state.set(Coord { x: 0, y: 0 }, 0);
+        state.inc_guess();
+        state.set(Coord { x: 1, y: 1 }, 2);
+
+        // the guesses made while checking are all rolled back, none of them can be left to save.
+        assert!(state.check_if_possible());
+        assert!(state.guesses().is_empty());
+
+        let read = read_session(&write_session(&state)).unwrap();
+        assert_eq!(read.nodes().vec(), state.nodes().vec());
+
+        // the same goes for guesses undone by dropping the guess level after a full solve.
+        assert!(state.full_solve());
+        state.dec_guess();
+        assert!(state.guesses().iter().all(|x| state.nodes().get(*x).is_found()));
+        assert!(read_session(&write_session(&state)).is_ok());
+    }
+
+    #[test]
+    fn repeated_marks() {
+        // a mark written twice is still there, rather than toggled off again.
+        let text = "sudoku session\nblock 2 2\nmark 1 2 3\nmark 1 2 3\nmark 1 2 1\n";
+        let state = read_session(text).unwrap();
+
+        assert_eq!(state.marks(Coord { x: 2, y: 1 }).iter().collect::<Vec<_>>(), vec![0, 2]);
+    }
+
+    #[test]
+    fn levels_and_duplicates() {
+        // a node solved at level 2 can't be in a session that is only at level 1.
+        let text = "sudoku session\nblock 2 2\nlevel 1\ncell 0 0 1 2\n";
+        assert!(matches!(read_session(text), Err(FormatError::BadLine(4))));
+
+        let text = "sudoku session\nblock 2 2\nlevel 1\neliminated 0 0 1 2\n";
+        assert!(matches!(read_session(text), Err(FormatError::BadLine(4))));
+
+        let text = "sudoku session\nblock 2 2\ncell 0 0 1 0\ncell 0 0 2 0\n";
+        assert!(matches!(read_session(text), Err(FormatError::BadLine(4))));
+
+        let text = "sudoku session\nblock 3 2\nwindows\n";
+        assert!(matches!(read_session(text), Err(FormatError::NotSquare(3))));
+    }
+
+    #[test]
+    fn overlapping_cages() {
+        let text = "sudoku session\nblock 2 2\ncage 3 0 0 0 1\ncage 7 0 1 1 1\n";
+        assert!(matches!(read_session(text), Err(FormatError::BadLine(4))));
+
+        // a node can't be in the same cage twice either.
+        let text = "sudoku session\nblock 2 2\ncage 3 0 0 0 0\n";
+        assert!(matches!(read_session(text), Err(FormatError::BadLine(3))));
+    }
+}
@@ -1,35 +1,79 @@
 use crate::prelude::*;
-use crate::graphics::{SmallGrid, List, StatusBar, Help, PopUp};
-use crate::state::State;
+use crate::graphics::{Grid, SmallGrid, BigGrid, List, StatusBar, Help, PopUp, char_num};
+use crate::state::{State, History, Region, Solutions};
+use crate::formats::{Puzzle, save_session, load_session};
+use crate::rating::Rating;
+use crate::hint::Hint;
+use crate::dlx::Dlx;
+
+use std::path::{Path, PathBuf};
 
 use frames::{prelude::*, ManagerTrait};
 use frames::layout_manager::LayoutManager;
 use frames::crossterm::event::{KeyCode, KeyModifiers};
 
+///Largest puzzle rated while playing, rating bigger ones can take too long when they need guessing.
+const MAX_RATED: usize = 16;
+
 pub struct Game {
-    grid:   SmallGrid,
-    status: StatusBar,
-    help:   Help,
-    list:   List,
-    state:  State,
-    popup:  PopUp,
+    grid:          SmallGrid,
+    big_grid:      BigGrid,
+    big:           bool,
+    status:        StatusBar,
+    help:          Help,
+    list:          List,
+    state:         State,
+    history:       History<State>,
+    popup:         PopUp,
+    session:       PathBuf,
+    marking:       bool,
+    hide_possible: bool,
+    ///The givens the status bar rating is for.
+    rated:         Option<Puzzle>,
 }
 
 impl Game {
     pub fn new(manager: &mut LayoutManager, grid: SmallGrid, list: List) -> Self {
+        let mut big_grid = BigGrid::new(manager, grid.get_size());
+        big_grid.enabled(false);
+
         Self {
-            state:  State::new(grid.get_size()),
-            status: StatusBar::new(manager),
-            help:   Help::new(manager),
-            popup:  PopUp::new(manager),
+            state:         State::new(grid.get_size()),
+            history:       History::new(),
+            big:           false,
+            status:        StatusBar::new(manager),
+            help:          Help::new(manager),
+            popup:         PopUp::new(manager),
+            session:       PathBuf::from("sudoku.session"),
+            marking:       false,
+            hide_possible: false,
+            rated:         None,
             grid,
+            big_grid,
             list,
         }
     }
 
+    ///Replaces the puzzle being played and resizes the grid to match.
+    pub fn set_state(&mut self, state: State) {
+        self.grid.resize(state.sub_size());
+        self.big_grid.resize(state.sub_size());
+        self.state = state;
+        self.history.clear();
+        self.update_blocks();
+        self.update_rating();
+        self.main_update();
+    }
+
+    ///Sets the file used by the save and load keys.
+    pub fn set_session(&mut self, path: &Path) {
+        self.session = path.to_path_buf();
+    }
+
     pub fn main(&mut self, manager: &mut LayoutManager) {
         self.list.main();
-        self.status.update(&self.state);
+        self.update_rating();
+        self.main_update();
 
         loop {
             manager.draw().unwrap();
@@ -45,10 +89,18 @@ impl Game {
                         }
                         self.popup.disable();
                     },
-                    KeyEvent{code: Left, ..}  => { self.grid.move_by(Coord { x: -1, y:  0 }) },
-                    KeyEvent{code: Right, ..} => { self.grid.move_by(Coord { x:  1, y:  0 }) },
-                    KeyEvent{code: Up, ..}    => { self.grid.move_by(Coord { x:  0, y: -1 }) },
-                    KeyEvent{code: Down, ..}  => { self.grid.move_by(Coord { x:  0, y:  1 }) },
+                    KeyEvent{code: Left, ..}  => { self.grid().move_by(Coord { x: -1, y:  0 }) },
+                    KeyEvent{code: Right, ..} => { self.grid().move_by(Coord { x:  1, y:  0 }) },
+                    KeyEvent{code: Up, ..}    => { self.grid().move_by(Coord { x:  0, y: -1 }) },
+                    KeyEvent{code: Down, ..}  => { self.grid().move_by(Coord { x:  0, y:  1 }) },
+                    KeyEvent{code: Char('z'), modifiers: KeyModifiers::CONTROL, ..} => {
+                        self.undo();
+                        self.main_update();
+                    }
+                    KeyEvent{code: Char('y'), modifiers: KeyModifiers::CONTROL, ..} => {
+                        self.redo();
+                        self.main_update();
+                    }
                     KeyEvent{code: Char(c), ..} => {
                         match c {
                             '-' | '_' => {
@@ -63,37 +115,79 @@ impl Game {
                             'i' | 'I' => {
                                 self.insert_mode(manager);
                                 self.list.main();
+                                self.update_rating();
+                                self.main_update();
                             }
                             'B' => {
-                                self.state.loop_basic();
+                                self.change(|x| x.loop_basic());
                                 self.main_update();
                             }
                             'b' => {
-                                self.state.solve_basic();
+                                self.change(|x| x.solve_basic());
                                 self.main_update();
                             }
                             'E' => {
-                                self.state.loop_exclusive();
+                                self.change(|x| x.loop_exclusive());
                                 self.main_update();
                             }
                             'e' => {
-                                self.state.solve_exclusive();
+                                self.change(|x| x.solve_exclusive());
+                                self.main_update();
+                            }
+                            'X' => {
+                                self.change(|x| x.loop_intersections());
+                                self.main_update();
+                            }
+                            'x' => {
+                                self.change(|x| x.solve_intersections());
+                                self.main_update();
+                            }
+                            'P' => {
+                                self.change(|x| x.loop_subsets());
+                                self.main_update();
+                            }
+                            'p' => {
+                                self.change(|x| x.solve_subsets());
                                 self.main_update();
                             }
                             'f' => {
-                                self.state.full_solve_no_guessing();
+                                self.change(|x| x.full_solve_no_guessing());
                                 self.main_update();
                             }
                             'F' => {
-                                self.state.full_solve();
+                                self.change(|x| x.full_solve());
                                 self.main_update();
                             }
+                            'v' | 'V' => {
+                                self.switch_grid(manager.size());
+                            }
                             'c' | 'C' => {
                                 self.popup.possible(self.state.check_if_possible());
                                 manager.draw().unwrap();
                                 manager.get_input();
                                 self.popup.disable();
                             }
+                            '?' => {
+                                self.hint(manager);
+                                self.list.main();
+                            }
+                            'u' | 'U' => {
+                                let solutions = self.solutions();
+                                self.popup.solutions(&solutions);
+                                self.wait_popup(manager);
+                            }
+                            's' | 'S' => {
+                                self.save(manager);
+                            }
+                            'l' | 'L' => {
+                                self.load(manager);
+                            }
+                            'r' | 'R' => {
+                                self.block_mode(manager);
+                                self.list.main();
+                                self.update_rating();
+                                self.main_update();
+                            }
                             _ => {}
                         }
                     }
@@ -118,20 +212,71 @@ impl Game {
         }
     }
 
+    fn save(&mut self, manager: &mut LayoutManager) {
+        match save_session(&self.state, &self.session) {
+            Ok(()) => { self.popup.message("Session saved.") }
+            Err(err) => { self.popup.message(&format!("Could not save: {}", err)) }
+        }
+
+        self.wait_popup(manager);
+    }
+
+    fn load(&mut self, manager: &mut LayoutManager) {
+        match load_session(&self.session) {
+            Ok(state) => {
+                self.set_state(state);
+                self.popup.message("Session loaded.");
+            }
+            Err(err) => { self.popup.message(&format!("Could not load: {}", err)) }
+        }
+
+        self.wait_popup(manager);
+    }
+
+    fn wait_popup(&mut self, manager: &mut LayoutManager) {
+        manager.draw().unwrap();
+        manager.get_input();
+        self.popup.disable();
+    }
+
+    ///Points out the simplest next deduction and applies it if the user confirms with enter.
+    fn hint(&mut self, manager: &mut LayoutManager) {
+        let hint = match Hint::find(&self.state) {
+            Some(hint) => { hint }
+            None => {
+                self.popup.message("No simple deduction found.");
+                self.wait_popup(manager);
+                return
+            }
+        };
+
+        self.grid().set_highlight(&hint.cells);
+        self.list.hint(&hint);
+        self.main_update();
+        manager.draw().unwrap();
+
+        if let Input::KeyBoard(KeyEvent{code: KeyCode::Enter, ..}) = manager.get_input() {
+            self.change(|x| hint.apply(x));
+        }
+
+        self.grid().set_highlight(&[]);
+        self.main_update();
+    }
+
     fn help(&mut self, manager: &mut LayoutManager) {
-        self.grid.enabled(false);
+        self.grid().enabled(false);
         self.status.enabled(false);
         self.list.enabled(false);
 
         self.help.main(manager);
 
-        self.grid.enabled(true);
+        self.grid().enabled(true);
         self.status.enabled(true);
         self.list.enabled(true);
     }
 
     pub fn insert_mode(&mut self, manager: &mut LayoutManager) {
-        self.grid.pointer_on(manager.size());
+        self.grid().pointer_on(manager.size());
         self.update_list();
 
         loop {
@@ -142,25 +287,25 @@ impl Game {
                 match x {
                     KeyEvent{code: Esc, ..} => { break; },
                     KeyEvent{code: Left, modifiers: KeyModifiers::SHIFT, ..} => {
-                        self.grid.move_by(Coord { x: -1, y:  0 })
+                        self.grid().move_by(Coord { x: -1, y:  0 })
                     }
                     KeyEvent{code: Left, ..} => {
                         self.set_pointer(Coord { x: -1, y:  0 }, manager.size())
                     },
                     KeyEvent{code: Right, modifiers: KeyModifiers::SHIFT, ..} => {
-                        self.grid.move_by(Coord { x:  1, y:  0 })
+                        self.grid().move_by(Coord { x:  1, y:  0 })
                     }
                     KeyEvent{code: Right, ..} => {
                         self.set_pointer(Coord { x:  1, y:  0 }, manager.size())
                     },
                     KeyEvent{code: Up, modifiers: KeyModifiers::SHIFT, ..} => {
-                        self.grid.move_by(Coord { x:  0, y: -1 })
+                        self.grid().move_by(Coord { x:  0, y: -1 })
                     }
                     KeyEvent{code: Up, ..} => {
                         self.set_pointer(Coord { x:  0, y: -1 }, manager.size())
                     },
                     KeyEvent{code: Down, modifiers: KeyModifiers::SHIFT, ..} => {
-                        self.grid.move_by(Coord { x:  0, y:  1 })
+                        self.grid().move_by(Coord { x:  0, y:  1 })
                     }
                     KeyEvent{code: Down, ..} => {
                         self.set_pointer(Coord { x:  0, y:  1 }, manager.size())
@@ -168,21 +313,51 @@ impl Game {
                     KeyEvent{code: Backspace, ..} => {
                         self.clear();
                     }
+                    KeyEvent{code: Char('z'), modifiers: KeyModifiers::CONTROL, ..} => {
+                        self.undo();
+                        self.insert_update();
+                    }
+                    KeyEvent{code: Char('y'), modifiers: KeyModifiers::CONTROL, ..} => {
+                        self.redo();
+                        self.insert_update();
+                    }
+                    KeyEvent{code: Tab, ..} => {
+                        self.marking = !self.marking;
+                        self.update_list();
+                    }
+                    KeyEvent{code: Char(c), modifiers: KeyModifiers::ALT, ..} => {
+                        if let Some(num) = char_num(c) {
+                            if num < self.state.size() {
+                                self.mark(num);
+                            }
+                        }
+                    }
                     KeyEvent{code: Char(c), ..} => {
                         match c {
                             '1'..='9' | 'a'..='w' => {
-                                if let Some(num) = char_num(c) {
-                                    if num < self.state.size() {
+                                if let Some(num) = char_num(c).filter(|x| *x < self.state.size()) {
+                                    if self.marking {
+                                        self.mark(num);
+                                    }
+                                    else {
                                         self.set(num);
                                     }
                                 }
                             }
+                            '.' => {
+                                self.hide_possible = !self.hide_possible;
+                                self.insert_update();
+                            }
                             '-' => {
                                 self.dec_guess();
                             }
                             '=' => {
                                 self.inc_guess();
                             }
+                            '?' => {
+                                self.hint(manager);
+                                self.update_list();
+                            }
                             _ => {}
                         }
                     }
@@ -191,58 +366,209 @@ impl Game {
             }
         }
 
-        self.grid.pointer_off();
+        self.grid().pointer_off();
+    }
+
+    ///Paints which block each node is in, for jigsaw puzzles. The new blocks are only used once
+    ///enter is pressed and every block is the right size and in one piece.
+    fn block_mode(&mut self, manager: &mut LayoutManager) {
+        let mut blocks = self.state.blocks();
+
+        self.grid().pointer_on(manager.size());
+        self.update_block_list(&blocks);
+
+        loop {
+            manager.draw().unwrap();
+
+            if let Input::KeyBoard(x) = manager.get_input() {
+                use KeyCode::*;
+                let change = match x.code {
+                    Esc => { break; }
+                    Enter => {
+                        let mut changed = self.state.clone();
+
+                        match changed.set_blocks(&blocks) {
+                            Ok(()) => {
+                                self.change(|x| *x = changed);
+                                break;
+                            }
+                            Err(err) => {
+                                self.popup.message(&format!("Can't use the blocks, {}.", err));
+                                self.wait_popup(manager);
+                            }
+                        }
+
+                        continue;
+                    }
+                    Left  => { Coord { x: -1, y:  0 } }
+                    Right => { Coord { x:  1, y:  0 } }
+                    Up    => { Coord { x:  0, y: -1 } }
+                    Down  => { Coord { x:  0, y:  1 } }
+                    Char(c) => {
+                        if let Some(block) = char_num(c).filter(|x| *x < self.state.size()) {
+                            let pos = self.grid().pointer();
+                            *blocks.get_mut(pos) = block;
+
+                            let cages = self.state.cages().to_vec();
+                            let edges = self.state.edges().to_vec();
+                            self.grid().set_layout(&blocks, &cages, &edges);
+                            self.update_grid();
+                        }
+
+                        Coord { x: 0, y: 0 }
+                    }
+                    _ => { Coord { x: 0, y: 0 } }
+                };
+
+                self.grid().inc_pointer(change, manager.size());
+                self.update_block_list(&blocks);
+            }
+        }
+
+        self.grid().pointer_off();
+        self.update_blocks();
+    }
+
+    fn update_block_list(&mut self, blocks: &Vec2D<usize>) {
+        let pos = self.grid().pointer();
+        self.list.blocks(pos, *blocks.get(pos), Region::blocks(blocks).err());
+    }
+
+    ///Draws the block edges and cages of both grids from the state, for when the blocks may have changed shape.
+    fn update_blocks(&mut self) {
+        let blocks = self.state.blocks();
+
+        self.grid.set_layout(&blocks, self.state.cages(), self.state.edges());
+        self.big_grid.set_layout(&blocks, self.state.cages(), self.state.edges());
     }
 
     fn inc_guess(&mut self) {
-        self.state.inc_guess();
+        self.change(|x| x.inc_guess());
         self.main_update()
     }
 
     fn dec_guess(&mut self) {
-        self.state.dec_guess();
+        self.change(|x| x.dec_guess());
         self.main_update()
     }
 
     fn update_list(&mut self) {
-        let pos = self.grid.pointer();
+        let pos = self.grid().pointer();
+        let node = self.state.nodes().get(pos);
+
+        let reasons: Vec<String> = node.conflicts()
+            .map(|list| list.iter().map(|x| self.state.relation(pos, *x).unwrap_or_default()).collect())
+            .unwrap_or_default();
 
         self.list.node(
             pos,
-            self.state.nodes().get(pos)
+            node,
+            &reasons,
+            self.state.marks(pos),
+            self.marking,
+            !self.hide_possible
         )
     }
 
+    ///Changes the state in a way that can be undone.
+    fn change<R>(&mut self, change: impl FnOnce(&mut State) -> R) -> R {
+        self.history.change(&mut self.state, change)
+    }
+
+    fn undo(&mut self) {
+        self.history.undo(&mut self.state);
+        self.update_blocks();
+    }
+
+    fn redo(&mut self) {
+        self.history.redo(&mut self.state);
+        self.update_blocks();
+    }
+
+    ///Finds up to two ways to finish the puzzle with the exact cover solver where it suits the puzzle.
+    fn solutions(&mut self) -> Solutions {
+        if Dlx::suits(&self.state) {
+            Solutions::new(Dlx::new(&self.state).solutions(2).1)
+        }
+        else {
+            self.state.solutions()
+        }
+    }
+
+    ///The grid that is currently being shown.
+    fn grid(&mut self) -> &mut dyn Grid {
+        if self.big { &mut self.big_grid } else { &mut self.grid }
+    }
+
+    fn update_grid(&mut self) {
+        if self.big {
+            self.big_grid.set_show_possible(!self.hide_possible);
+            self.big_grid.update(&self.state);
+        }
+        else {
+            self.grid.update(&self.state);
+        }
+    }
+
+    ///Swaps between drawing one character per node and drawing the possibilities inside each node.
+    fn switch_grid(&mut self, size: Coord) {
+        let pointer = self.grid().pointer();
+        self.grid().enabled(false);
+
+        self.big = !self.big;
+
+        self.grid().enabled(true);
+        self.grid().set_pointer(pointer, size);
+        self.update_grid();
+    }
+
     fn set_pointer(&mut self, change: Coord, size: Coord) {
-        self.grid.inc_pointer(change, size);
+        self.grid().inc_pointer(change, size);
         self.update_list();
     }
 
+    ///Rates the nodes solved at guess level 0, which should be the givens. Nothing is solved
+    ///again unless the givens or constraints have changed since the last rating.
+    fn update_rating(&mut self) {
+        let givens = Puzzle::from_state(&self.state);
+        if self.rated.as_ref() == Some(&givens) { return }
+
+        if givens.size() > MAX_RATED {
+            self.status.set_unrated();
+        }
+        else {
+            self.status.set_rating(&Rating::new(&givens));
+        }
+
+        self.rated = Some(givens);
+    }
+
     fn main_update(&mut self) {
         self.status.update(&self.state);
-        self.grid.update(self.state.nodes());
+        self.update_grid();
     }
 
     fn insert_update(&mut self) {
-        self.grid.update(self.state.nodes());
+        self.update_grid();
         self.update_list();
         self.status.update(&self.state);
     }
 
     fn set(&mut self, solve: usize) {
-        self.state.set(self.grid.pointer(), solve);
+        let pos = self.grid().pointer();
+        self.change(|x| x.set(pos, solve));
         self.insert_update();
     }
 
-    fn clear(&mut self) {
-        self.state.clear(self.grid.pointer());
+    fn mark(&mut self, num: usize) {
+        let pos = self.grid().pointer();
+        self.change(|x| x.toggle_mark(pos, num));
         self.insert_update();
     }
-}
 
-fn char_num(c: char) -> Option<usize> {
-    match c {
-        '1'..='9' | 'a'..='w' => { Some((c.to_digit(32).unwrap() - 1) as usize) }
-        _ => { None }
+    fn clear(&mut self) {
+        let pos = self.grid().pointer();
+        self.change(|x| x.clear(pos));
+        self.insert_update();
     }
 }
\ No newline at end of file
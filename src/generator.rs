@@ -0,0 +1,236 @@
+use crate::prelude::*;
+use crate::formats::Puzzle;
+use crate::random::Random;
+use crate::rating::{Rating, Difficulty};
+use crate::dlx::Dlx;
+use crate::state::Chess;
+
+///The pattern the givens of a generated puzzle are kept in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Symmetry {
+    None,
+    ///Unchanged by a half turn.
+    Rotational,
+    ///Unchanged by flipping left to right.
+    Mirror,
+    ///Unchanged by flipping along the main diagonal.
+    Diagonal,
+}
+
+impl Symmetry {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "none"       => { Some(Self::None) }
+            "rotational" => { Some(Self::Rotational) }
+            "mirror"     => { Some(Self::Mirror) }
+            "diagonal"   => { Some(Self::Diagonal) }
+            _ => { None }
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None       => { "none" }
+            Self::Rotational => { "rotational" }
+            Self::Mirror     => { "mirror" }
+            Self::Diagonal   => { "diagonal" }
+        }
+    }
+
+    ///The symmetry after this one, for cycling through them.
+    pub fn next(&self) -> Self {
+        match self {
+            Self::None       => { Self::Rotational }
+            Self::Rotational => { Self::Mirror }
+            Self::Mirror     => { Self::Diagonal }
+            Self::Diagonal   => { Self::None }
+        }
+    }
+
+    ///The nodes that have to be removed together with pos to keep the pattern.
+    fn group(&self, pos: Coord, size: i32) -> Vec<Coord> {
+        let last = size - 1;
+
+        let other = match self {
+            Self::None       => { pos }
+            Self::Rotational => { Coord { x: last - pos.x, y: last - pos.y } }
+            Self::Mirror     => { Coord { x: last - pos.x, y: pos.y } }
+            Self::Diagonal   => { Coord { x: pos.y, y: pos.x } }
+        };
+
+        if other == pos { vec![pos] } else { vec![pos, other] }
+    }
+}
+
+///Makes puzzles with exactly one solution.
+pub struct Generator {
+    random:   Random,
+    symmetry: Symmetry,
+    diagonal: bool,
+    windows:  bool,
+    chess:    Vec<Chess>,
+}
+
+impl Generator {
+    pub fn new(seed: u64, symmetry: Symmetry) -> Self {
+        Self {
+            random:   Random::new(seed),
+            symmetry,
+            diagonal: false,
+            windows:  false,
+            chess:    Vec::new(),
+        }
+    }
+
+    ///Makes the following puzzles need every number once along both diagonals too.
+    pub fn set_diagonal(&mut self, diagonal: bool) {
+        self.diagonal = diagonal;
+    }
+
+    ///Makes the following puzzles need every number once in the windows of a windoku too.
+    ///Only for square blocks.
+    pub fn set_windows(&mut self, windows: bool) {
+        self.windows = windows;
+    }
+
+    ///Makes the following puzzles keep nodes one of these chess moves apart from sharing a number.
+    pub fn set_chess(&mut self, chess: &[Chess]) {
+        self.chess = chess.to_vec();
+    }
+
+    ///Returns None if no grid fits the constraints, like anti-king with 2x2 blocks.
+    pub fn generate(&mut self, sub_size: Coord) -> Option<Puzzle> {
+        let solution = self.fill(sub_size)?;
+        Some(self.remove_givens(&solution))
+    }
+
+    ///Keeps generating puzzles until one is rated at the target difficulty, giving up after the given number of attempts.
+    pub fn generate_difficulty(&mut self, sub_size: Coord, target: Difficulty, attempts: usize) -> Option<Puzzle> {
+        for _ in 0..attempts {
+            let puzzle = self.generate(sub_size)?;
+
+            if Rating::new(&puzzle).difficulty() == Some(target) {
+                return Some(puzzle)
+            }
+        }
+
+        None
+    }
+
+    ///Makes a random finished grid with the exact cover solver picking numbers at random.
+    fn fill(&mut self, sub_size: Coord) -> Option<Puzzle> {
+        let mut puzzle = Puzzle::new(sub_size);
+        puzzle.diagonal = self.diagonal;
+        puzzle.windows = self.windows;
+        puzzle.chess = self.chess.clone();
+
+        let grid = Dlx::new(&puzzle.to_state()).solve_random(&mut self.random)?;
+
+        for pos in CoordIter::new(Coord { x: 0, y: 0 }, grid.size()) {
+            *puzzle.givens.get_mut(pos) = Some(*grid.get(pos));
+        }
+
+        Some(puzzle)
+    }
+
+    ///Removes givens in a random order, putting them back whenever the puzzle stops having a single solution.
+    fn remove_givens(&mut self, solution: &Puzzle) -> Puzzle {
+        let mut state = solution.to_state();
+        let size = state.size() as i32;
+
+        let mut order: Vec<Coord> = CoordIter::new(Coord { x: 0, y: 0 }, Coord::same(size)).collect();
+        self.random.shuffle(&mut order);
+
+        for pos in order {
+            if !state.nodes().get(pos).is_found() { continue; }
+
+            let group = self.symmetry.group(pos, size);
+
+            for cell in &group {
+                state.clear(*cell);
+            }
+
+            if Dlx::new(&state).count(2) != 1 {
+                for cell in &group {
+                    state.set(*cell, solution.givens.get(*cell).unwrap());
+                }
+            }
+        }
+
+        Puzzle::from_state(&state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::Candidates;
+
+    #[test]
+    fn unique_and_symmetric() {
+        for symmetry in [Symmetry::None, Symmetry::Rotational, Symmetry::Mirror, Symmetry::Diagonal] {
+            let puzzle = Generator::new(1, symmetry).generate(Coord { x: 3, y: 3 }).unwrap();
+
+            assert_eq!(puzzle.to_state().count_solutions(2).0, 1);
+
+            for pos in CoordIter::new(Coord { x: 0, y: 0 }, puzzle.givens.size()) {
+                for other in symmetry.group(pos, 9) {
+                    assert_eq!(puzzle.givens.get(pos).is_some(), puzzle.givens.get(other).is_some());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn variants() {
+        // diagonal, windows and chess rules of each case.
+        let cases: [(bool, bool, &[Chess]); 3] = [
+            (true,  false, &[]),
+            (false, true,  &[]),
+            (false, false, &[Chess::Knight]),
+        ];
+
+        for (diagonal, windows, chess) in cases {
+            let mut generator = Generator::new(3, Symmetry::Rotational);
+            generator.set_diagonal(diagonal);
+            generator.set_windows(windows);
+            generator.set_chess(chess);
+
+            let puzzle = generator.generate(Coord { x: 3, y: 3 }).unwrap();
+            assert_eq!((puzzle.diagonal, puzzle.windows, &puzzle.chess[..]), (diagonal, windows, chess));
+
+            let mut state = puzzle.to_state();
+            assert_eq!(state.count_solutions(2).0, 1);
+            assert!(state.full_solve() && !state.has_conflicts());
+
+            // every region, diagonals and windows included, holds each number once.
+            for region in state.regions() {
+                let nums: Candidates = region.cells.iter().filter_map(|pos| state.nodes().get(*pos).get_num()).collect();
+                assert_eq!(nums.count(), region.cells.len());
+            }
+
+            for pos in CoordIter::new(Coord { x: 0, y: 0 }, state.nodes().size()) {
+                for other in puzzle.chess.iter().flat_map(|x| x.moves(pos, 9)) {
+                    assert_ne!(state.nodes().get(pos).get_num(), state.nodes().get(other).get_num());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn impossible() {
+        let mut generator = Generator::new(1, Symmetry::Rotational);
+        generator.set_chess(&[Chess::King]);
+
+        assert!(generator.generate(Coord { x: 2, y: 2 }).is_none());
+        assert!(generator.generate_difficulty(Coord { x: 2, y: 2 }, Difficulty::Easy, 5).is_none());
+    }
+
+    #[test]
+    fn same_seed() {
+        let first = Generator::new(42, Symmetry::Rotational).generate(Coord { x: 3, y: 2 }).unwrap();
+        let second = Generator::new(42, Symmetry::Rotational).generate(Coord { x: 3, y: 2 }).unwrap();
+
+        assert_eq!(first.givens.vec(), second.givens.vec());
+    }
+}
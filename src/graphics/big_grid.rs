@@ -0,0 +1,315 @@
+use frames::frame_types::basic;
+use frames::modifiers::position;
+use frames::prelude::*;
+use frames::layout_manager::*;
+use basic::IBasic;
+
+use crate::prelude::*;
+use crate::state::{State, Node, Region, Cage, Edge};
+
+use super::{num_char, Grid};
+use super::small_grid::{flip_colors, to_range, edge_left, edge_above, cage_map, cage_left, cage_above, edge_char};
+
+const FOCUS:     Color = Color::Rgb { r: 40, g: 110, b: 40 };
+const HIGHLIGHT: Color = Color::Rgb { r: 40, g: 60, b: 110 };
+const POSSIBLE:  Color = Color::DarkGrey;
+const MARK:      Color = Color::Cyan;
+const CAGE:      Color = Color::Rgb { r: 190, g: 160, b: 70 };
+const EDGE:      Color = Color::Rgb { r: 140, g: 190, b: 230 };
+
+///Draws every node as a sub_size.x by sub_size.y grid of its possibilities, with found nodes in the middle.
+pub struct BigGrid {
+    frame:      basic::Basic,
+    pos:        position::Position,
+    sub_size:   Coord,
+    size:       Coord,
+    pointer:    Coord,
+    pointer_on: bool,
+    highlight:  Vec<Coord>,
+    ///False when the solver's possibilities are kept hidden, leaving only the pencil marks.
+    possible:   bool,
+}
+
+impl BigGrid {
+    pub fn new(man: &mut LayoutManager, sub_size: Coord) -> BigGrid {
+        let size = Coord{x: 0, y: 0};
+        let frame = basic::new(size, Vec::new()).unwrap();
+        let pos = position::craft().pos(Coord { x: 0, y: 1 }).size(size).done();
+
+        man.layout.borrow_mut()
+            .objects.push(Object { frame: frame.clone(), pos: pos.clone() });
+
+        let mut temp = BigGrid {
+            frame,
+            pos,
+            sub_size,
+            size,
+            pointer: Coord { x: 0, y: 0 },
+            pointer_on: false,
+            highlight: Vec::new(),
+            possible: true,
+        };
+
+        temp.resize(sub_size);
+        temp
+    }
+
+    ///Takes effect from the next update.
+    pub fn set_show_possible(&mut self, show_possible: bool) {
+        self.possible = show_possible;
+    }
+
+    ///Top left corner of a node.
+    fn translate(&self, pos: Coord) -> Coord {
+        Coord {
+            x: 1 + pos.x * (self.sub_size.x + 1),
+            y: 1 + pos.y * (self.sub_size.y + 1),
+        }
+    }
+
+    ///Every character a node is drawn with.
+    fn cell_iter(&self, pos: Coord) -> CoordIter {
+        let start = self.translate(pos);
+        CoordIter::new(start, start + self.sub_size)
+    }
+
+    fn background(&self, pos: Coord) -> Color {
+        match self.highlight.iter().position(|x| *x == pos) {
+            Some(0) => { FOCUS }
+            Some(_) => { HIGHLIGHT }
+            None => { Color::Black }
+        }
+    }
+
+    fn flip_cell(&self, frame: &mut IBasic, pos: Coord) {
+        for loc_pos in self.cell_iter(pos) {
+            flip_colors(frame, loc_pos);
+        }
+    }
+
+    fn move_for_pointer(&mut self, mut size: Coord) {
+        let mut pos = self.pos.borrow_mut();
+
+        size.x -= super::LIST_SIZE + 2 + self.sub_size.x;
+        size.y -= 2 + self.sub_size.y;
+        let pointer = self.translate(self.pointer) + pos.data.pos;
+
+        let shift = Coord {
+            x: to_range(pointer.x, 1, size.x),
+            y: to_range(pointer.y, 2, size.y),
+        };
+
+        pos.data.pos += shift;
+    }
+}
+
+impl Grid for BigGrid {
+    fn resize(&mut self, sub_size: Coord) {
+        self.sub_size = sub_size;
+        self.set_layout(&Region::block_map(sub_size), &[], &[]);
+    }
+
+    fn set_layout(&mut self, blocks: &Vec2D<usize>, cages: &[Cage], edges: &[Edge]) {
+        let sub_size = self.sub_size;
+        let cells = sub_size.x * sub_size.y;
+        let map = cage_map(cages, cells);
+        let size = Coord {
+            x: 1 + cells * (sub_size.x + 1),
+            y: 1 + cells * (sub_size.y + 1),
+        };
+        let area = size.x * size.y;
+
+        let mut borrowed = self.frame.borrow_mut();
+        borrowed.replace(size, vec![Pixel::Clear; area as usize]).unwrap();
+
+        for pos in CoordIter::new(Coord { x: 0, y: 0 }, size) {
+            let on_row = pos.y % (sub_size.y + 1) == 0;
+            let on_col = pos.x % (sub_size.x + 1) == 0;
+
+            if !on_row && !on_col { continue; }
+
+            // the node below and to the right of the line.
+            let node = Coord { x: pos.x / (sub_size.x + 1), y: pos.y / (sub_size.y + 1) };
+
+            let up = on_col && pos.y > 0;
+            let down = on_col && pos.y < size.y - 1;
+            let left = on_row && pos.x > 0;
+            let right = on_row && pos.x < size.x - 1;
+
+            // lines along block edges are brighter than lines between nodes.
+            let block_edge = if on_row && on_col {
+                (up && edge_left(blocks, Coord { x: node.x, y: node.y - 1 }))
+                    || (down && edge_left(blocks, node))
+                    || (left && edge_above(blocks, Coord { x: node.x - 1, y: node.y }))
+                    || (right && edge_above(blocks, node))
+            }
+            else if on_row {
+                edge_above(blocks, node)
+            }
+            else {
+                edge_left(blocks, node)
+            };
+
+            // cage edges between nodes are dotted, block edges hide them.
+            let cage_edge = match (on_row, on_col) {
+                (true, false) => { !block_edge && cage_above(&map, node) }
+                (false, true) => { !block_edge && cage_left(&map, node) }
+                _ => { false }
+            };
+
+            let pixel = if cage_edge {
+                Pixel::new(if on_row { '┄' } else { '┆' }, CAGE, Color::Black)
+            }
+            else {
+                let fg = if block_edge { Color::White } else { POSSIBLE };
+                Pixel::new(line_char(up, down, left, right), fg, Color::Black)
+            };
+
+            borrowed.set_pixel(pos, pixel);
+        }
+
+        // sums go on the line above the top left node of each cage.
+        for cage in cages {
+            let start = self.translate(cage.corner()) - Coord { x: 0, y: 1 };
+
+            for (i, c) in cage.sum.to_string().chars().enumerate() {
+                borrowed.set_pixel(Coord { x: start.x + i as i32, y: start.y }, Pixel::new(c, CAGE, Color::Black));
+            }
+        }
+
+        // marks go in the middle of the line between their two nodes.
+        for edge in edges {
+            let pos = if edge.a.y == edge.b.y {
+                let right = self.translate(Coord { x: edge.a.x.max(edge.b.x), y: edge.a.y });
+                Coord { x: right.x - 1, y: right.y + sub_size.y / 2 }
+            }
+            else {
+                let lower = self.translate(Coord { x: edge.a.x, y: edge.a.y.max(edge.b.y) });
+                Coord { x: lower.x + sub_size.x / 2, y: lower.y - 1 }
+            };
+
+            borrowed.set_pixel(pos, Pixel::new(edge_char(edge), EDGE, Color::Black));
+        }
+
+        self.pos.borrow_mut().data.size = size;
+        self.size = size;
+    }
+
+    fn get_size(&self) -> Coord {
+        self.sub_size
+    }
+
+    fn enabled(&mut self, enabled: bool) {
+        self.pos.borrow_mut().data.enabled = enabled;
+    }
+
+    fn set_highlight(&mut self, cells: &[Coord]) {
+        self.highlight = cells.to_vec();
+    }
+
+    fn update(&mut self, state: &State) {
+        let nodes = state.nodes();
+        let mut frame = self.frame.borrow_mut();
+
+        for pos in CoordIter::new(Coord { x: 0, y: 0 }, nodes.size()) {
+            let bg = self.background(pos);
+            let corner = self.translate(pos);
+            let middle = corner + Coord { x: self.sub_size.x / 2, y: self.sub_size.y / 2 };
+            let marks = state.marks(pos);
+
+            for loc_pos in self.cell_iter(pos) {
+                let inner = loc_pos - corner;
+                let num = (inner.y * self.sub_size.x + inner.x) as usize;
+
+                let pixel = match nodes.get(pos) {
+                    Node::Found(data) => {
+                        let fg = if data.conflicts.is_empty() { Color::White } else { Color::Red };
+                        let c = if loc_pos == middle { num_char(data.num) } else { ' ' };
+
+                        Pixel::new(c, fg, bg)
+                    }
+                    Node::Possible(list) => {
+                        if marks[num] {
+                            Pixel::new(num_char(num), MARK, bg)
+                        }
+                        else if self.possible && list[num] {
+                            Pixel::new(num_char(num), POSSIBLE, bg)
+                        }
+                        else {
+                            Pixel::new(' ', Color::White, bg)
+                        }
+                    }
+                };
+
+                frame.set_pixel(loc_pos, pixel);
+            }
+        }
+
+        if self.pointer_on {
+            self.flip_cell(&mut frame, self.pointer);
+        }
+    }
+
+    fn move_by(&mut self, amount: Coord) {
+        self.pos.borrow_mut().data.pos += amount;
+    }
+
+    fn pointer(&self) -> Coord {
+        self.pointer
+    }
+
+    fn pointer_on(&mut self, size: Coord) {
+        self.move_for_pointer(size);
+        let mut frame = self.frame.borrow_mut();
+        self.pointer_on = true;
+
+        self.flip_cell(&mut frame, self.pointer);
+    }
+
+    fn pointer_off(&mut self) {
+        let mut frame = self.frame.borrow_mut();
+        self.pointer_on = false;
+
+        self.flip_cell(&mut frame, self.pointer);
+    }
+
+    fn set_pointer(&mut self, pos: Coord, size: Coord) {
+        if self.pointer_on {
+            let mut frame = self.frame.borrow_mut();
+
+            self.flip_cell(&mut frame, self.pointer);
+            self.flip_cell(&mut frame, pos);
+        }
+
+        self.pointer = pos;
+        self.move_for_pointer(size);
+    }
+
+    fn inc_pointer(&mut self, amount: Coord, screen_size: Coord) {
+        let size = self.sub_size.x * self.sub_size.y;
+        let pos = Coord {
+            x: (((self.pointer.x + amount.x) % size) + size) %size,
+            y: (((self.pointer.y + amount.y) % size) + size) %size,
+        };
+
+        self.set_pointer(pos, screen_size);
+    }
+}
+
+///Box drawing character joining the given directions.
+pub(super) fn line_char(up: bool, down: bool, left: bool, right: bool) -> char {
+    match (up, down, left, right) {
+        (false, true,  false, true ) => { '┌' }
+        (false, true,  true,  false) => { '┐' }
+        (true,  false, false, true ) => { '└' }
+        (true,  false, true,  false) => { '┘' }
+        (true,  true,  false, true ) => { '├' }
+        (true,  true,  true,  false) => { '┤' }
+        (false, true,  true,  true ) => { '┬' }
+        (true,  false, true,  true ) => { '┴' }
+        (true,  true,  true,  true ) => { '┼' }
+        (true,  true,  false, false) => { '│' }
+        (false, false, false, false) => { ' ' }
+        _ => { '─' }
+    }
+}
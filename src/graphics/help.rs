@@ -36,6 +36,21 @@ impl Help {
                 \n"
             ));
 
+            borrowed.entries.push_back(Entry::new(
+                "Size Select:\
+                \nThe first screen, where the size of a new puzzle is picked.\
+                \nArrow Keys: Change the block size\
+                \nEnter: Enter the puzzle by hand\
+                \nG: Generate a puzzle with a single solution\
+                \nS: Change the symmetry of the generated puzzle's given numbers\
+                \nD: Make both diagonals need every number once, shaded in the grid. Only for square blocks\
+                \nW: Windoku, extra windows the size of a block that need every number once, tinted in the grid. Only for square blocks\
+                \nN: Anti-knight, nodes a knight's move apart can't have the same number\
+                \nK: Anti-king, nodes a king's move apart, including diagonally, can't have the same number\
+                \nLarger puzzles can take a while to generate.\
+                \n"
+            ));
+
             borrowed.entries.push_back(Entry::new(
                 "Main Menu:\
                 \nGeneral overview of the puzzle and where larger choices are provided.
@@ -50,7 +65,15 @@ impl Help {
                 \nShift + E: Loop Exclusive Solve\
                 \nF: Full Solve Without Guessing\
                 \nShift F: Full Solve With Guessing\
+                \nV: Switch between the small grid and the big grid\
+                \nCtrl + Z: Undo\
+                \nCtrl + Y: Redo\
                 \nC: Check if puzzle is currently possible.\
+                \nU: Check if puzzle has a unique solution.\
+                \n?: Hint\
+                \nS: Save session\
+                \nL: Load session\
+                \nR: Edit the blocks for jigsaw puzzles\
                 \n"
             ));
 
@@ -63,6 +86,54 @@ impl Help {
                 \n-: Decrease guess Level\
                 \n1-9, A-P: Solve node under the pointer with the selected number\\letter\
                 \nBackspace: Clear a solved node\
+                \nTab: Switch between entering solutions and pencil marks\
+                \nAlt + 1-9, A-P: Toggle a pencil mark on the node under the pointer\
+                \n.: Show or hide the possible solutions found by the solvers\
+                \nCtrl + Z: Undo\
+                \nCtrl + Y: Redo\
+                \n?: Hint\
+                \n"
+            ));
+
+            borrowed.entries.push_back(Entry::new(
+                "Big Grid:\
+                \nThe big grid draws each node as a small grid of its possibilities, the same shape as a block.\
+                \nPossibilities found by the solvers are grey and your pencil marks are cyan.\
+                \nHiding the possibilities in insertion mode also hides them here.\
+                \n"
+            ));
+
+            borrowed.entries.push_back(Entry::new(
+                "Pencil Marks:\
+                \nPencil marks are your own notes of what a node could be, kept apart from the possibilities the solvers find.\
+                \nThe list shows both for the node under the pointer, hide the solver's possibilities to avoid giving away the answer.\
+                \nPencil marks are saved with the session.\
+                \n"
+            ));
+
+            borrowed.entries.push_back(Entry::new(
+                "Jigsaw Blocks:\
+                \nIn jigsaw puzzles the blocks can be any shape, as long as each has one node for every number and is in one piece.\
+                \nPress R to edit them, then move the pointer and press 1-9, A-P to put the node in that block.\
+                \nEnter uses the new blocks once they are all valid, Esc leaves them as they were.\
+                \nJigsaw puzzles can be loaded from '.jigsaw' files, which have a grid of block symbols followed by a blank line and the givens.\
+                \n"
+            ));
+
+            borrowed.entries.push_back(Entry::new(
+                "Killer Cages:\
+                \nIn killer puzzles the nodes of each cage, outlined with dots, add up to the sum written at its top left and can't repeat a number.\
+                \nThe solvers narrow cages down by the sets of numbers that make their sums, and by what is left of each row, column and block once the cages inside it are taken out.\
+                \nKiller puzzles can be loaded from '.killer' files, which have the givens followed by a line for each cage, e.g. 'cage 15 r1c1 r1c2 r2c1'.\
+                \n"
+            ));
+
+            borrowed.entries.push_back(Entry::new(
+                "Edge Marks:\
+                \nMarks between two neighbouring nodes limit their numbers. A black dot '●' means one is double the other, a white dot '○' that they are one apart, X that they add up to 10 and V to 5.\
+                \nGreater-than signs '<', '>', '∧' and '∨' open towards the larger number.\
+                \nWith the negative constraint, dots, X and V are shown everywhere they hold, so neighbours without one can't fit it.\
+                \nMarks can be added to '.kropki', '.killer' or '.variant' files, which all use the same variant format, with lines like 'white r1c1 r1c2', 'greater r1c1 r2c1' for a larger first node, and 'negative black white'.\
                 \n"
             ));
 
@@ -77,6 +148,14 @@ impl Help {
                 \n"
             ));
 
+            borrowed.entries.push_back(Entry::new(
+                "Undo:\
+                \nEvery change to the puzzle can be undone with Ctrl + Z and redone with Ctrl + Y, including solves, pencil marks and guess level changes.\
+                \nUndoing a decrease in guess level brings back the nodes it cleared.\
+                \nThe last 100 changes are kept, loading a session forgets them.\
+                \n"
+            ));
+
             borrowed.entries.push_back(Entry::new(
                 "Basic Solve:\
                 \nThe basic solve simply looks at the possibilities of each node and if it only has one possibility it will solve it.\
@@ -89,6 +168,23 @@ impl Help {
                 \n"
             ));
 
+            borrowed.entries.push_back(Entry::new(
+                "Intersection Solve:\
+                \nIntersection solve looks where blocks cross rows and columns.\
+                \nIf a number can only go in one row or column of a block, it is removed from the rest of that row or column.\
+                \nIf a number can only go in one block of a row or column, it is removed from the rest of that block.\
+                \n"
+            ));
+
+            borrowed.entries.push_back(Entry::new(
+                "Subset Solve:\
+                \nSubset solve looks for pairs, triples and quads in each row column and block and removes the possibilities they rule out.\
+                \nNaked: when some nodes can only be the same few numbers between them, no other node in the group can be those numbers.\
+                \nHidden: when some numbers can only go in the same few nodes, those nodes can't be anything else.\
+                \nRemoved possibilities are kept until the guess level they were removed at is decreased.\
+                \n"
+            ));
+
             borrowed.entries.push_back(Entry::new(
                 "Full Solve:\
                 \nFull solve uses all of the available solves to complete the puzzle.\
@@ -97,6 +193,29 @@ impl Help {
                 \n"
             ));
 
+            borrowed.entries.push_back(Entry::new(
+                "Hint:\
+                \nFinds the simplest next step and explains it without solving anything.\
+                \nThe node that can be solved is shown in green and the nodes that explain why are shown in blue.\
+                \nPress enter to apply the hint or any other key to leave the puzzle as it is.\
+                \n"
+            ));
+
+            borrowed.entries.push_back(Entry::new(
+                "Unique Solution:\
+                \nChecks how many ways the puzzle can be finished from its current state.\
+                \nA proper puzzle has exactly one solution, if there are more it shows a node that differs between two of them.\
+                \n"
+            ));
+
+            borrowed.entries.push_back(Entry::new(
+                "Sessions:\
+                \nSaving writes the whole puzzle to a session file, including the guess level of every node, so it can be picked up later without losing any guesses.\
+                \nThe session file is next to the puzzle file if one was opened, otherwise it is 'sudoku.session' in the current directory.\
+                \nA session can also be opened from the command line by giving it in place of a puzzle file.\
+                \n"
+            ));
+
             borrowed.entries.push_back(Entry::new(
                 "About:\
                 \nSource Code: https://github.com/CircuitFire/sudoku
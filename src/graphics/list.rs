@@ -3,7 +3,9 @@ use frames::prelude::*;
 use frames::layout_manager::*;
 use frames::frame_types::text;
 
-use crate::state::Node;
+use crate::state::{Node, Candidates, BlockError, Chess};
+use crate::generator::Symmetry;
+use crate::hint::Hint;
 
 use super::num_char;
 
@@ -49,7 +51,10 @@ impl List {
         }
     }
 
-    pub fn node(&mut self, pos: Coord, node: &Node) {
+    ///Shows a node under the pointer. `marking` is true while digits toggle pencil marks,
+    ///and the solver's possibilities are only listed when `show_possible` is true.
+    ///`reasons` says why the node can't share its number with each of its conflicts, in the same order.
+    pub fn node(&mut self, pos: Coord, node: &Node, reasons: &[String], marks: &Candidates, marking: bool, show_possible: bool) {
         let mut frame = self.frame.borrow_mut();
         frame.entries.clear();
 
@@ -59,6 +64,10 @@ impl List {
             "Node: (y: {}, x: {})\n", pos.y, pos.x
         )));
 
+        frame.entries.push_back(Entry::new(format!(
+            "Entering (Tab): {}\n", if marking { "pencil marks" } else { "solutions" }
+        )));
+
         match &node {
             Node::Found(data) => {
                 frame.entries.push_back(Entry::new(format!(
@@ -70,8 +79,8 @@ impl List {
 
                 let mut temp = "Conflicts with:".to_string();
 
-                for conf in &data.conflicts {
-                    temp.push_str(&format!("\n(y: {}, x: {})", conf.y , conf.x))
+                for (conf, reason) in data.conflicts.iter().zip(reasons) {
+                    temp.push_str(&format!("\n(y: {}, x: {}) {}", conf.y , conf.x, reason))
                 }
 
                 frame.entries.push_back(Entry::new( temp ));
@@ -81,26 +90,86 @@ impl List {
                     "Unsolved:\n"
                 ));
 
-                let mut temp = "Possible Solutions:\n".to_string();
-
-                let mut iter = list.iter()
-                    .enumerate()
-                    .filter(|x| *x.1);
+                frame.entries.push_back(Entry::new(format!(
+                    "Pencil Marks:\n{}\n", num_list(marks)
+                )));
 
-                if let Some((num, _)) = iter.next() {
-                    temp.push(num_char(num));
+                if show_possible {
+                    frame.entries.push_back(Entry::new(format!(
+                        "Possible Solutions (. to hide):\n{}", num_list(list)
+                    )));
                 }
-
-                for (num, _) in iter {
-                    temp.push_str(&format!(", {}", num_char(num)))
+                else {
+                    frame.entries.push_back(Entry::new(
+                        "Possible Solutions hidden (. to show)"
+                    ));
                 }
-
-                frame.entries.push_back(Entry::new( temp ));
             },
         }
     }
 
-    pub fn show_size(&mut self, sub_size: Coord) {
+    pub fn hint(&mut self, hint: &Hint) {
+        let mut frame = self.frame.borrow_mut();
+        frame.entries.clear();
+        frame.indent = text::Indent::Hanging(2);
+
+        frame.entries.push_back(Entry::new(
+            "Hint:\n"
+        ));
+        frame.entries.push_back(Entry::new(
+            format!("{}\n", hint.description())
+        ));
+        frame.entries.push_back(Entry::new(
+            "Enter:\nApply the hint"
+        ));
+        frame.entries.push_back(Entry::new(
+            "Any other key:\nCancel"
+        ));
+    }
+
+    ///Shows the block editor, `problem` is why the blocks can't be used yet.
+    pub fn blocks(&mut self, pos: Coord, block: usize, problem: Option<BlockError>) {
+        let mut frame = self.frame.borrow_mut();
+        frame.entries.clear();
+        frame.indent = text::Indent::Hanging(2);
+
+        frame.entries.push_back(Entry::new(
+            "Block Editor:\n"
+        ));
+        frame.entries.push_back(Entry::new(format!(
+            "Node: (y: {}, x: {})", pos.y, pos.x
+        )));
+        frame.entries.push_back(Entry::new(format!(
+            "Block: {}\n", num_char(block)
+        )));
+
+        match problem {
+            Some(err) => {
+                frame.entries.push_back(Entry::new(format!(
+                    "Problem:\n{}\n", err
+                )));
+            }
+            None => {
+                frame.entries.push_back(Entry::new(
+                    "Blocks are ready\n"
+                ));
+            }
+        }
+
+        frame.entries.push_back(Entry::new(
+            "1-9, A-P:\nPut the node in that block"
+        ));
+        frame.entries.push_back(Entry::new(
+            "Enter:\nUse the blocks"
+        ));
+        frame.entries.push_back(Entry::new(
+            "Esc:\nCancel"
+        ));
+    }
+
+    ///`diagonal` and `windows` are true when the diagonals or windoku windows will need every number,
+    ///and `chess` holds the moves that can't join two of the same number.
+    pub fn show_size(&mut self, sub_size: Coord, symmetry: Symmetry, diagonal: bool, windows: bool, chess: &[Chess]) {
         let mut frame = self.frame.borrow_mut();
         frame.entries.clear();
 
@@ -114,7 +183,34 @@ impl List {
             format!("Size: {}x{}", size, size)
         ));
         frame.entries.push_back(Entry::new(
-            format!("Block size: {}x{}", sub_size.x, sub_size.y)
+            format!("Block size: {}x{}\n", sub_size.x, sub_size.y)
+        ));
+        frame.entries.push_back(Entry::new(
+            "G: Generate a puzzle"
+        ));
+        frame.entries.push_back(Entry::new(
+            format!("S: Symmetry: {}", symmetry.name())
+        ));
+
+        if sub_size.x == sub_size.y {
+            frame.entries.push_back(Entry::new(
+                format!("D: Diagonals: {}", if diagonal { "on" } else { "off" })
+            ));
+            frame.entries.push_back(Entry::new(
+                format!("W: Windoku: {}", if windows { "on" } else { "off" })
+            ));
+        }
+        else {
+            frame.entries.push_back(Entry::new(
+                "D, W: Diagonals and windoku\nneed square blocks"
+            ));
+        }
+
+        frame.entries.push_back(Entry::new(
+            format!("N: Anti-knight: {}", if chess.contains(&Chess::Knight) { "on" } else { "off" })
+        ));
+        frame.entries.push_back(Entry::new(
+            format!("K: Anti-king: {}", if chess.contains(&Chess::King) { "on" } else { "off" })
         ));
     }
 
@@ -141,19 +237,86 @@ impl List {
         frame.entries.push_back(Entry::new(
             "E (Shift Loop):\nExclusive Solve"
         ));
+        frame.entries.push_back(Entry::new(
+            "X (Shift Loop):\nIntersection Solve"
+        ));
+        frame.entries.push_back(Entry::new(
+            "P (Shift Loop):\nSubset Solve"
+        ));
         frame.entries.push_back(Entry::new(
             "F:\nFull Solve no guessing"
         ));
         frame.entries.push_back(Entry::new(
             "F + Shift:\nFull Solve"
         ));
+        frame.entries.push_back(Entry::new(
+            "V:\nSwitch grid view"
+        ));
+        frame.entries.push_back(Entry::new(
+            "Ctrl + Z / Y:\nUndo / Redo"
+        ));
         frame.entries.push_back(Entry::new(
             "C:\nCheck if possible"
         ));
+        frame.entries.push_back(Entry::new(
+            "U:\nCheck for unique solution"
+        ));
+        frame.entries.push_back(Entry::new(
+            "?:\nHint"
+        ));
+        frame.entries.push_back(Entry::new(
+            "S / L:\nSave / Load session"
+        ));
+        frame.entries.push_back(Entry::new(
+            "R:\nEdit blocks (jigsaw)"
+        ));
         
     }
 
+    ///Keys for playing a multi-grid puzzle.
+    pub fn multi(&mut self) {
+        let mut frame = self.frame.borrow_mut();
+        frame.entries.clear();
+        frame.indent = text::Indent::Hanging(2);
+
+        frame.entries.push_back(Entry::new(
+            "Esc:\nExit"
+        ));
+        frame.entries.push_back(Entry::new(
+            "Arrow keys:\nMove Pointer"
+        ));
+        frame.entries.push_back(Entry::new(
+            "Shift + Arrow keys:\nMove Puzzle"
+        ));
+        frame.entries.push_back(Entry::new(
+            "Numbers / Backspace:\nSet / Clear node"
+        ));
+        frame.entries.push_back(Entry::new(
+            "- / =:\nDec / Inc Guess Level, givens are set at 0"
+        ));
+        frame.entries.push_back(Entry::new(
+            "Ctrl + F:\nFull Solve no guessing"
+        ));
+        frame.entries.push_back(Entry::new(
+            "Ctrl + G:\nFull Solve"
+        ));
+        frame.entries.push_back(Entry::new(
+            "Ctrl + Z / Y:\nUndo / Redo"
+        ));
+        frame.entries.push_back(Entry::new(
+            "Ctrl + S:\nSave puzzle"
+        ));
+    }
+
     pub fn enabled(&mut self, enabled: bool) {
         self.pos.borrow_mut().data.enabled = enabled;
     }
-}
\ No newline at end of file
+}
+
+///The numbers in the set, separated by commas.
+fn num_list(list: &Candidates) -> String {
+    list.iter()
+        .map(|num| num_char(num).to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -2,6 +2,12 @@
 mod small_grid;
 pub use small_grid::SmallGrid;
 
+mod big_grid;
+pub use big_grid::BigGrid;
+
+mod multi_grid;
+pub use multi_grid::MultiGrid;
+
 mod list;
 pub use list::{List, LIST_SIZE};
 
@@ -14,9 +20,31 @@ pub use help::Help;
 mod popup;
 pub use popup::PopUp;
 
-use frames::prelude::Color;
+use frames::prelude::{Color, Coord};
+use crate::prelude::Vec2D;
+use crate::state::{State, Cage, Edge};
 pub const BORDER: Color = Color::Rgb { r: 20, g: 20, b: 20 };
 
+///A view of the puzzle with a pointer that can be moved over the nodes.
+pub trait Grid {
+    ///Changes the size of the puzzle, with the usual rectangle blocks.
+    fn resize(&mut self, sub_size: Coord);
+    ///Draws the edges of blocks of any shape, given as which block each node is in,
+    ///the dotted outlines of killer cages with their sums and the marks between neighbouring nodes.
+    fn set_layout(&mut self, blocks: &Vec2D<usize>, cages: &[Cage], edges: &[Edge]);
+    fn get_size(&self) -> Coord;
+    fn enabled(&mut self, enabled: bool);
+    fn update(&mut self, state: &State);
+    fn move_by(&mut self, amount: Coord);
+    fn pointer(&self) -> Coord;
+    fn pointer_on(&mut self, size: Coord);
+    fn pointer_off(&mut self);
+    fn set_pointer(&mut self, pos: Coord, size: Coord);
+    fn inc_pointer(&mut self, amount: Coord, screen_size: Coord);
+    ///Marks nodes with a different background from the next update, the first node is the one being focused on.
+    fn set_highlight(&mut self, cells: &[Coord]);
+}
+
 pub fn num_char(num: usize) -> char {
     match num {
         0..=32 => {
@@ -26,4 +54,11 @@ pub fn num_char(num: usize) -> char {
             '?'
         }
     }
-}
\ No newline at end of file
+}
+
+pub fn char_num(c: char) -> Option<usize> {
+    match c {
+        '1'..='9' | 'a'..='w' | 'A'..='W' => { Some((c.to_digit(32).unwrap() - 1) as usize) }
+        _ => { None }
+    }
+}
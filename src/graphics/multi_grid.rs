@@ -0,0 +1,219 @@
+use frames::frame_types::basic;
+use frames::modifiers::position;
+use frames::prelude::*;
+use frames::layout_manager::*;
+
+use crate::prelude::*;
+use crate::state::MultiState;
+
+use super::num_char;
+use super::big_grid::line_char;
+use super::small_grid::{layout, flip_colors, to_range};
+
+///The grids of a multi-grid puzzle drawn together in their combined shape, with lines at the
+///edges of every block. The pointer moves over the whole puzzle and skips the gaps between grids.
+pub struct MultiGrid {
+    frame:      basic::Basic,
+    pos:        position::Position,
+    ///Which nodes of the whole puzzle are in some grid.
+    cells:      Vec2D<bool>,
+    pointer:    Coord,
+    pointer_on: bool,
+    columns:    Vec<i32>,
+    rows:       Vec<i32>,
+}
+
+impl MultiGrid {
+    pub fn new(man: &mut LayoutManager, multi: &MultiState) -> Self {
+        let size = Coord { x: 0, y: 0 };
+        let frame = basic::new(size, Vec::new()).unwrap();
+        let pos = position::craft().pos(Coord { x: 0, y: 1 }).size(size).done();
+
+        man.layout.borrow_mut()
+            .objects.push(Object { frame: frame.clone(), pos: pos.clone() });
+
+        let mut cells = Vec2D::new(multi.size(), false);
+
+        for pos in multi.all_iter() {
+            *cells.get_mut(pos) = true;
+        }
+
+        let mut temp = Self {
+            frame,
+            pos,
+            cells,
+            pointer: Coord { x: 0, y: 0 },
+            pointer_on: false,
+            columns: Vec::new(),
+            rows: Vec::new(),
+        };
+
+        temp.draw_lines(multi.sub_size());
+        temp
+    }
+
+    fn contains(&self, pos: Coord) -> bool {
+        let size = self.cells.size();
+        (0..size.x).contains(&pos.x) && (0..size.y).contains(&pos.y) && *self.cells.get(pos)
+    }
+
+    fn translate(&self, pos: Coord) -> Coord {
+        Coord {
+            x: self.columns[pos.x as usize],
+            y: self.rows[pos.y as usize],
+        }
+    }
+
+    // the grids start on block edges, so every line falls between blocks of the whole puzzle.
+    // a line is only drawn beside nodes that are in some grid.
+    fn draw_lines(&mut self, sub_size: Coord) {
+        let cells = self.cells.size();
+        let colors = ColorSet {
+            fg: Color::White,
+            bg: Color::Black,
+        };
+
+        let col_lines: Vec<bool> = (0..=cells.x).map(|x| x % sub_size.x == 0).collect();
+        let row_lines: Vec<bool> = (0..=cells.y).map(|y| y % sub_size.y == 0).collect();
+
+        let (col_pos, columns) = layout(&col_lines);
+        let (row_pos, rows) = layout(&row_lines);
+
+        let size = Coord {
+            x: col_pos[cells.x as usize] + 1,
+            y: row_pos[cells.y as usize] + 1,
+        };
+        let area = size.x * size.y;
+
+        // edges beside the node, along its left side and its top.
+        let left = |pos: Coord| col_lines[pos.x as usize] && (self.contains(pos) || self.contains(pos - Coord { x: 1, y: 0 }));
+        let above = |pos: Coord| row_lines[pos.y as usize] && (self.contains(pos) || self.contains(pos - Coord { x: 0, y: 1 }));
+
+        let mut borrowed = self.frame.borrow_mut();
+        borrowed.replace(size, vec![Pixel::Clear; area as usize]).unwrap();
+
+        for pos in CoordIter::new(Coord { x: 0, y: 0 }, cells) {
+            if self.contains(pos) {
+                borrowed.set_pixel(Coord { x: columns[pos.x as usize], y: rows[pos.y as usize] }, Pixel::new_color_set(' ', colors));
+            }
+        }
+
+        for x in 0..=cells.x {
+            for y in 0..cells.y {
+                if left(Coord { x, y }) {
+                    borrowed.set_pixel(Coord { x: col_pos[x as usize], y: rows[y as usize] }, Pixel::new_color_set('│', colors));
+                }
+            }
+        }
+
+        for y in 0..=cells.y {
+            for x in 0..cells.x {
+                if above(Coord { x, y }) {
+                    borrowed.set_pixel(Coord { x: columns[x as usize], y: row_pos[y as usize] }, Pixel::new_color_set('─', colors));
+                }
+            }
+
+            for x in (0..=cells.x).filter(|x| col_lines[*x as usize]) {
+                let c = line_char(
+                    y > 0 && left(Coord { x, y: y - 1 }),
+                    y < cells.y && left(Coord { x, y }),
+                    x > 0 && above(Coord { x: x - 1, y }),
+                    x < cells.x && above(Coord { x, y }),
+                );
+
+                if c != ' ' {
+                    borrowed.set_pixel(Coord { x: col_pos[x as usize], y: row_pos[y as usize] }, Pixel::new_color_set(c, colors));
+                }
+            }
+        }
+
+        self.pos.borrow_mut().data.size = size;
+        self.columns = columns;
+        self.rows = rows;
+    }
+
+    fn move_for_pointer(&mut self, mut size: Coord) {
+        let mut pos = self.pos.borrow_mut();
+
+        size.x -= super::LIST_SIZE + 2;
+        size.y -= 2;
+        let pointer = self.translate(self.pointer) + pos.data.pos;
+
+        let shift = Coord {
+            x: to_range(pointer.x, 1, size.x),
+            y: to_range(pointer.y, 2, size.y),
+        };
+
+        pos.data.pos += shift;
+    }
+
+    pub fn enabled(&mut self, enabled: bool) {
+        self.pos.borrow_mut().data.enabled = enabled;
+    }
+
+    pub fn update(&mut self, multi: &MultiState) {
+        let mut frame = self.frame.borrow_mut();
+
+        for pos in multi.all_iter() {
+            let loc_pos = self.translate(pos);
+            let node = multi.node(pos).unwrap();
+
+            // a shared node conflicts if it does in any of its grids.
+            let conflicts = multi.places(pos).any(|(i, inner)| multi.grids()[i].nodes().get(inner).has_conflicts());
+            let fg = if conflicts { Color::Red } else { Color::White };
+
+            frame.set_char(loc_pos, node.get_num().map_or(' ', num_char));
+            frame.set_colors(loc_pos, ColorSet { fg, bg: Color::Black });
+        }
+
+        if self.pointer_on {
+            flip_colors(&mut frame, self.translate(self.pointer));
+        }
+    }
+
+    pub fn move_by(&mut self, amount: Coord) {
+        self.pos.borrow_mut().data.pos += amount;
+    }
+
+    pub fn pointer(&self) -> Coord {
+        self.pointer
+    }
+
+    pub fn pointer_on(&mut self, size: Coord) {
+        self.move_for_pointer(size);
+        let mut frame = self.frame.borrow_mut();
+        let pos = self.translate(self.pointer);
+        self.pointer_on = true;
+
+        flip_colors(&mut frame, pos);
+    }
+
+    pub fn pointer_off(&mut self) {
+        let mut frame = self.frame.borrow_mut();
+        let pos = self.translate(self.pointer);
+        self.pointer_on = false;
+
+        flip_colors(&mut frame, pos);
+    }
+
+    pub fn set_pointer(&mut self, pos: Coord, size: Coord) {
+        if self.pointer_on {
+            let mut frame = self.frame.borrow_mut();
+            let old_pos = self.translate(self.pointer);
+            let new_pos = self.translate(pos);
+
+            flip_colors(&mut frame, old_pos);
+            flip_colors(&mut frame, new_pos);
+        }
+
+        self.pointer = pos;
+        self.move_for_pointer(size);
+    }
+
+    ///Moves the pointer by the amount, carrying on in the same direction over the gaps between
+    ///grids and wrapping around the edges of the whole puzzle.
+    pub fn inc_pointer(&mut self, multi: &MultiState, amount: Coord, screen_size: Coord) {
+        let pos = multi.step(self.pointer, amount);
+        self.set_pointer(pos, screen_size);
+    }
+}
@@ -4,6 +4,11 @@ use frames::layout_manager::*;
 use frames::frame_types::text;
 use frames::frame_types::basic::{self, IBasic};
 
+use crate::prelude::*;
+use crate::state::Solutions;
+
+use super::num_char;
+
 const TEXT_SIZE: Coord = Coord { x: 40, y: 5 };
 const BOX_SIZE: Coord = Coord { x: 42, y: 7 };
 
@@ -112,6 +117,62 @@ impl PopUp {
         self.text_box.borrow_mut().entries[2].set_text("");
     }
 
+    pub fn solutions(&mut self, solutions: &Solutions) {
+        self.border_pos.borrow_mut().data.enabled = true;
+        self.text_pos.borrow_mut().data.enabled   = true;
+
+        let (message, detail) = match solutions {
+            Solutions::None => {
+                ("No Solutions.".to_string(), String::new())
+            }
+            Solutions::Unique(_) => {
+                ("Unique Solution.".to_string(), String::new())
+            }
+            Solutions::Multiple(first, second) => {
+                let pos = CoordIter::new(Coord { x: 0, y: 0 }, first.size())
+                    .find(|x| first.get(*x) != second.get(*x))
+                    .unwrap();
+
+                (
+                    "Multiple Solutions.".to_string(),
+                    format!("(y: {}, x: {}) can be {} or {}",
+                        pos.y, pos.x,
+                        num_char(*first.get(pos)),
+                        num_char(*second.get(pos))
+                    )
+                )
+            }
+        };
+
+        self.text_box.borrow_mut().entries[0].set_text(
+            format!("\n{:^width$}\n{:^width$}",
+                message,
+                detail,
+                width = (TEXT_SIZE.x - 1) as usize
+            )
+        );
+        self.text_box.borrow_mut().entries[1].set_text(
+            format!("{:^width$}", "[Any Key to continue]", width = TEXT_SIZE.x as usize)
+        );
+        self.text_box.borrow_mut().entries[2].set_text("");
+    }
+
+    pub fn message(&mut self, message: &str) {
+        self.border_pos.borrow_mut().data.enabled = true;
+        self.text_pos.borrow_mut().data.enabled   = true;
+
+        self.text_box.borrow_mut().entries[0].set_text(
+            format!("\n{:^width$}\n",
+                message,
+                width = (TEXT_SIZE.x - 1) as usize
+            )
+        );
+        self.text_box.borrow_mut().entries[1].set_text(
+            format!("{:^width$}", "[Any Key to continue]", width = TEXT_SIZE.x as usize)
+        );
+        self.text_box.borrow_mut().entries[2].set_text("");
+    }
+
     pub fn leaving(&mut self) {
         self.border_pos.borrow_mut().data.enabled = true;
         self.text_pos.borrow_mut().data.enabled   = true;
@@ -5,9 +5,17 @@ use frames::layout_manager::*;
 use basic::IBasic;
 
 use crate::prelude::*;
-use crate::state::Node;
+use crate::state::{State, Unit, Region, Cage, Edge, Mark};
 
-use super::num_char;
+use super::{num_char, Grid};
+use super::big_grid::line_char;
+
+const FOCUS:     Color = Color::Rgb { r: 40, g: 110, b: 40 };
+const HIGHLIGHT: Color = Color::Rgb { r: 40, g: 60, b: 110 };
+const SHADE:     Color = Color::Rgb { r: 60, g: 60, b: 60 };
+const WINDOW:    Color = Color::Rgb { r: 70, g: 45, b: 70 };
+const CAGE:      Color = Color::Rgb { r: 190, g: 160, b: 70 };
+const EDGE:      Color = Color::Rgb { r: 140, g: 190, b: 230 };
 
 pub struct SmallGrid {
     frame:      basic::Basic,
@@ -16,6 +24,11 @@ pub struct SmallGrid {
     size:       Coord,
     pointer:    Coord,
     pointer_on: bool,
+    highlight:  Vec<Coord>,
+    ///Where each column and row of nodes is drawn, lines are only left between them at block and cage edges
+    ///and where a mark sits between two nodes.
+    columns:    Vec<i32>,
+    rows:       Vec<i32>,
 }
 
 impl SmallGrid {
@@ -34,94 +47,235 @@ impl SmallGrid {
             size,
             pointer: Coord { x: 0, y: 0 },
             pointer_on: false,
+            highlight: Vec::new(),
+            columns: Vec::new(),
+            rows: Vec::new(),
         };
 
         temp.resize(sub_size);
         temp
     }
 
-    pub fn resize(&mut self, sub_size: Coord) {
-        let size = Coord {
-            x: 1 + ((sub_size.x + 1) * sub_size.y),
-            y: 1 + ((sub_size.y + 1) * sub_size.x),
+    fn translate(&self, pos: Coord) -> Coord {
+        Coord {
+            x: self.columns[pos.x as usize],
+            y: self.rows[pos.y as usize],
+        }
+    }
+
+    ///Nodes in extra regions get a tint, SHADE for the diagonals and WINDOW for windoku windows.
+    fn background(&self, pos: Coord, tint: Option<Color>) -> Color {
+        match self.highlight.iter().position(|x| *x == pos) {
+            Some(0) => { FOCUS }
+            Some(_) => { HIGHLIGHT }
+            None => { tint.unwrap_or(Color::Black) }
+        }
+    }
+
+    fn move_for_pointer(&mut self, mut size: Coord) {
+        let mut pos = self.pos.borrow_mut();
+
+        size.x -= super::LIST_SIZE + 2;
+        size.y -= 2;
+        let pointer = self.translate(self.pointer) + pos.data.pos;
+
+        let shift = Coord {
+            x: to_range(pointer.x, 1, size.x),
+            y: to_range(pointer.y, 2, size.y),
         };
-        let area = size.x * size.y;
+
+        pos.data.pos += shift;
+    }
+}
+
+impl Grid for SmallGrid {
+    fn resize(&mut self, sub_size: Coord) {
+        self.sub_size = sub_size;
+        self.set_layout(&Region::block_map(sub_size), &[], &[]);
+    }
+
+    // a line is left between two columns or rows of nodes wherever a block or cage edge runs between them,
+    // or a mark has to be drawn between two of their nodes.
+    fn set_layout(&mut self, blocks: &Vec2D<usize>, cages: &[Cage], edges: &[Edge]) {
+        let cells = blocks.size().x;
+        let map = cage_map(cages, cells);
         let colors = ColorSet {
             fg: Color::White,
             bg: Color::Black,
         };
+        let dotted = ColorSet {
+            fg: CAGE,
+            bg: Color::Black,
+        };
+        let marked = ColorSet {
+            fg: EDGE,
+            bg: Color::Black,
+        };
+
+        let col_lines: Vec<bool> = (0..=cells)
+            .map(|x| {
+                (0..cells).any(|y| edge_left(blocks, Coord { x, y }) || cage_left(&map, Coord { x, y }))
+                    || edges.iter().any(|e| e.a.y == e.b.y && e.a.x.max(e.b.x) == x)
+            })
+            .collect();
+        let row_lines: Vec<bool> = (0..=cells)
+            .map(|y| {
+                (0..cells).any(|x| edge_above(blocks, Coord { x, y }) || cage_above(&map, Coord { x, y }))
+                    || edges.iter().any(|e| e.a.x == e.b.x && e.a.y.max(e.b.y) == y)
+            })
+            .collect();
+
+        // screen position of each line, then of each node after it.
+        let (col_pos, columns) = layout(&col_lines);
+        let (row_pos, rows) = layout(&row_lines);
+
+        let size = Coord {
+            x: col_pos[cells as usize] + 1,
+            y: row_pos[cells as usize] + 1,
+        };
+        let area = size.x * size.y;
 
         let mut borrowed = self.frame.borrow_mut();
         borrowed.replace(size, vec![Pixel::Clear; area as usize]).unwrap();
 
-        //corners
-        {
-            let x = size.x - 1;
-            let y = size.y - 1;
+        for x in (0..=cells).filter(|x| col_lines[*x as usize]) {
+            for y in 0..cells {
+                let pos = Coord { x, y };
+                let pixel = if edge_left(blocks, pos) {
+                    Pixel::new_color_set('│', colors)
+                }
+                else if cage_left(&map, pos) {
+                    Pixel::new_color_set('┆', dotted)
+                }
+                else {
+                    Pixel::new_color_set(' ', colors)
+                };
 
-            borrowed.set_pixel(Coord{x: 0, y: 0}, Pixel::new_color_set('┌', colors));
-            borrowed.set_pixel(Coord{x: 0, y: y}, Pixel::new_color_set('└', colors));
-            borrowed.set_pixel(Coord{x: x, y: 0}, Pixel::new_color_set('┐', colors));
-            borrowed.set_pixel(Coord{x: x, y: y}, Pixel::new_color_set('┘', colors));
+                borrowed.set_pixel(Coord { x: col_pos[x as usize], y: rows[y as usize] }, pixel);
+            }
         }
-        
-        //rows
-        draw_row(&mut borrowed, colors, size.x, sub_size, 0, '─', '┬');
-        draw_row(&mut borrowed, colors, size.x, sub_size, size.y - 1, '─', '┴');
 
-        for y in 1..(size.y - 1) {
-            if y % (sub_size.y + 1) != 0 {
-                draw_row(&mut borrowed, colors, size.x, sub_size, y, ' ', '│');
+        for y in (0..=cells).filter(|y| row_lines[*y as usize]) {
+            for x in 0..cells {
+                let pos = Coord { x, y };
+                let pixel = if edge_above(blocks, pos) {
+                    Pixel::new_color_set('─', colors)
+                }
+                else if cage_above(&map, pos) {
+                    Pixel::new_color_set('┄', dotted)
+                }
+                else {
+                    Pixel::new_color_set(' ', colors)
+                };
+
+                borrowed.set_pixel(Coord { x: columns[x as usize], y: row_pos[y as usize] }, pixel);
+            }
+
+            for x in (0..=cells).filter(|x| col_lines[*x as usize]) {
+                let c = line_char(
+                    y > 0 && edge_left(blocks, Coord { x, y: y - 1 }),
+                    y < cells && edge_left(blocks, Coord { x, y }),
+                    x > 0 && edge_above(blocks, Coord { x: x - 1, y }),
+                    x < cells && edge_above(blocks, Coord { x, y }),
+                );
+
+                // where only cage edges meet, a dot keeps the outline going.
+                let cage_corner = (y > 0 && cage_left(&map, Coord { x, y: y - 1 }))
+                    || (y < cells && cage_left(&map, Coord { x, y }))
+                    || (x > 0 && cage_above(&map, Coord { x: x - 1, y }))
+                    || (x < cells && cage_above(&map, Coord { x, y }));
+
+                let pixel = if c == ' ' && cage_corner {
+                    Pixel::new_color_set('·', dotted)
+                }
+                else {
+                    Pixel::new_color_set(c, colors)
+                };
+
+                borrowed.set_pixel(Coord { x: col_pos[x as usize], y: row_pos[y as usize] }, pixel);
+            }
+        }
+
+        // sums go on the line above the top left node of each cage.
+        for cage in cages {
+            let corner = cage.corner();
+            let start = Coord { x: columns[corner.x as usize], y: row_pos[corner.y as usize] };
+
+            for (i, c) in cage.sum.to_string().chars().enumerate() {
+                let pos = Coord { x: start.x + i as i32, y: start.y };
+
+                if pos.x < size.x {
+                    borrowed.set_pixel(pos, Pixel::new_color_set(c, dotted));
+                }
+            }
+        }
+
+        // marks go on the line between their two nodes.
+        for edge in edges {
+            let pos = if edge.a.y == edge.b.y {
+                Coord { x: col_pos[edge.a.x.max(edge.b.x) as usize], y: rows[edge.a.y as usize] }
             }
             else {
-                draw_row(&mut borrowed, colors, size.x, sub_size, y, '─', '┼');
+                Coord { x: columns[edge.a.x as usize], y: row_pos[edge.a.y.max(edge.b.y) as usize] }
             };
-            
-        }
 
-        //columns
-        draw_col(&mut borrowed, colors, size.y, sub_size, 0, '│', '├');
-        draw_col(&mut borrowed, colors, size.y, sub_size, size.x - 1, '│', '┤');
+            borrowed.set_pixel(pos, Pixel::new_color_set(edge_char(edge), marked));
+        }
 
         self.pos.borrow_mut().data.size = size;
         self.size = size;
-        self.sub_size = sub_size;
+        self.columns = columns;
+        self.rows = rows;
     }
 
-    pub fn get_size(&self) -> Coord {
+    fn get_size(&self) -> Coord {
         self.sub_size
     }
 
-    pub fn enabled(&mut self, enabled: bool) {
+    fn enabled(&mut self, enabled: bool) {
         self.pos.borrow_mut().data.enabled = enabled;
     }
 
-    fn translate(&self, pos: Coord) -> Coord {
-        Coord {
-            x: 1 + pos.x + (pos.x / (self.sub_size.x)),
-            y: 1 + pos.y + (pos.y / (self.sub_size.y))
-        }
+    fn set_highlight(&mut self, cells: &[Coord]) {
+        self.highlight = cells.to_vec();
     }
 
-    pub fn update(&mut self, nodes: &Vec2D<Node>) {
+    fn update(&mut self, state: &State) {
+        let nodes = state.nodes();
         let mut frame = self.frame.borrow_mut();
 
+        let mut tints = Vec2D::new(nodes.size(), None);
+
+        for region in state.regions() {
+            let tint = match region.unit {
+                Unit::Diagonal(_) => { SHADE }
+                Unit::Window(_) => { WINDOW }
+                _ => { continue }
+            };
+
+            for pos in &region.cells {
+                *tints.get_mut(*pos) = Some(tint);
+            }
+        }
+
         for pos in CoordIter::new(Coord { x: 0, y: 0 }, nodes.size()) {
             let loc_pos = self.translate(pos);
             let node = nodes.get(pos);
+            let bg = self.background(pos, *tints.get(pos));
+
             if node.is_found() {
                 frame.set_char(loc_pos, num_char(node.get_num().unwrap()));
 
                 if node.has_conflicts() {
-                    frame.set_colors(loc_pos, ColorSet { fg: Color::Red, bg: Color::Black });
+                    frame.set_colors(loc_pos, ColorSet { fg: Color::Red, bg });
                 }
                 else {
-                    frame.set_colors(loc_pos, ColorSet { fg: Color::White, bg: Color::Black });
+                    frame.set_colors(loc_pos, ColorSet { fg: Color::White, bg });
                 }
             }
             else {
                 frame.set_char(loc_pos, ' ');
-                frame.set_colors(loc_pos, ColorSet { fg: Color::White, bg: Color::Black });
+                frame.set_colors(loc_pos, ColorSet { fg: Color::White, bg });
             }
         }
 
@@ -130,30 +284,15 @@ impl SmallGrid {
         }
     }
 
-    pub fn move_by(&mut self, amount: Coord) {
+    fn move_by(&mut self, amount: Coord) {
         self.pos.borrow_mut().data.pos += amount;
     }
 
-    pub fn move_for_pointer(&mut self, mut size: Coord) {
-        let mut pos = self.pos.borrow_mut();
-
-        size.x -= super::LIST_SIZE + 2;
-        size.y -= 2;
-        let pointer = self.translate(self.pointer) + pos.data.pos;
-
-        let shift = Coord {
-            x: to_range(pointer.x, 1, size.x),
-            y: to_range(pointer.y, 2, size.y),
-        };
-
-        pos.data.pos += shift;
-    }
-
-    pub fn pointer(&self) -> Coord {
+    fn pointer(&self) -> Coord {
         self.pointer
     }
 
-    pub fn pointer_on(&mut self, size: Coord) {
+    fn pointer_on(&mut self, size: Coord) {
         self.move_for_pointer(size);
         let mut frame = self.frame.borrow_mut();
         let pos = self.translate(self.pointer);
@@ -162,7 +301,7 @@ impl SmallGrid {
         flip_colors(&mut frame, pos);
     }
 
-    pub fn pointer_off(&mut self) {
+    fn pointer_off(&mut self) {
         let mut frame = self.frame.borrow_mut();
         let pos = self.translate(self.pointer);
         self.pointer_on = false;
@@ -170,7 +309,7 @@ impl SmallGrid {
         flip_colors(&mut frame, pos);
     }
 
-    pub fn set_pointer(&mut self, pos: Coord, size: Coord) {
+    fn set_pointer(&mut self, pos: Coord, size: Coord) {
         if self.pointer_on {
             let mut frame = self.frame.borrow_mut();
             let old_pos = self.translate(self.pointer);
@@ -184,7 +323,7 @@ impl SmallGrid {
         self.move_for_pointer(size);
     }
 
-    pub fn inc_pointer(&mut self, amount: Coord, screen_size: Coord) {
+    fn inc_pointer(&mut self, amount: Coord, screen_size: Coord) {
         let size = self.sub_size.x * self.sub_size.y;
         let pos = Coord {
             x: (((self.pointer.x + amount.x) % size) + size) %size,
@@ -195,31 +334,93 @@ impl SmallGrid {
     }
 }
 
-fn draw_row(basic: &mut IBasic, colors: ColorSet, size: i32, sub_size: Coord, y: i32, main: char, alt: char) {
-    for x in 1..(size - 1) {
-        let t = if x % (sub_size.x + 1) != 0 { main }
-                                              else { alt };
+///Takes which gaps between nodes get a line, including before the first and after the last node,
+///and returns where each line and each node goes. Gaps without a line aren't drawn.
+pub(super) fn layout(lines: &[bool]) -> (Vec<i32>, Vec<i32>) {
+    let mut line_pos = Vec::new();
+    let mut node_pos = Vec::new();
+    let mut next = 0;
+
+    for (i, line) in lines.iter().enumerate() {
+        line_pos.push(next);
+        if *line { next += 1; }
+
+        if i < lines.len() - 1 {
+            node_pos.push(next);
+            next += 1;
+        }
+    }
+
+    (line_pos, node_pos)
+}
+
+///True if a block edge runs along the left side of the node. The edges of the grid count,
+///so x can be one past the last column.
+pub(super) fn edge_left(blocks: &Vec2D<usize>, pos: Coord) -> bool {
+    let cells = blocks.size().x;
+    pos.x == 0 || pos.x == cells || blocks.get(pos) != blocks.get(Coord { x: pos.x - 1, y: pos.y })
+}
+
+///True if a block edge runs along the top of the node, y can be one past the last row.
+pub(super) fn edge_above(blocks: &Vec2D<usize>, pos: Coord) -> bool {
+    let cells = blocks.size().y;
+    pos.y == 0 || pos.y == cells || blocks.get(pos) != blocks.get(Coord { x: pos.x, y: pos.y - 1 })
+}
+
+///Which cage each node is in, if any.
+pub(super) fn cage_map(cages: &[Cage], cells: i32) -> Vec2D<Option<usize>> {
+    let mut map = Vec2D::new(Coord::same(cells), None);
 
-        basic.set_pixel(Coord{x, y}, Pixel::new_color_set(t, colors));
+    for (i, cage) in cages.iter().enumerate() {
+        for pos in &cage.cells {
+            *map.get_mut(*pos) = Some(i);
+        }
     }
+
+    map
+}
+
+///True if a cage edge runs along the left side of the node, x can be one past the last column.
+pub(super) fn cage_left(map: &Vec2D<Option<usize>>, pos: Coord) -> bool {
+    let cells = map.size().x;
+    let cage = |x: i32| if (0..cells).contains(&x) { *map.get(Coord { x, y: pos.y }) } else { None };
+
+    cage(pos.x) != cage(pos.x - 1)
 }
 
-fn draw_col(basic: &mut IBasic, colors: ColorSet, size: i32, sub_size: Coord, x: i32, main: char, alt: char) {
-    for y in 1..(size - 1) {
-        let t = if y % (sub_size.y + 1) != 0 { main }
-                                              else { alt };
+///True if a cage edge runs along the top of the node, y can be one past the last row.
+pub(super) fn cage_above(map: &Vec2D<Option<usize>>, pos: Coord) -> bool {
+    let cells = map.size().y;
+    let cage = |y: i32| if (0..cells).contains(&y) { *map.get(Coord { x: pos.x, y }) } else { None };
 
-        basic.set_pixel(Coord{x, y}, Pixel::new_color_set(t, colors));
+    cage(pos.y) != cage(pos.y - 1)
+}
+
+///How a mark is drawn, greater-than signs open towards the larger number.
+pub(super) fn edge_char(edge: &Edge) -> char {
+    match edge.mark {
+        Mark::Black   => { '●' }
+        Mark::White   => { '○' }
+        Mark::X       => { 'X' }
+        Mark::V       => { 'V' }
+        Mark::Greater => {
+            match (edge.a.y == edge.b.y, edge.a.x < edge.b.x || edge.a.y < edge.b.y) {
+                (true, true)   => { '>' }
+                (true, false)  => { '<' }
+                (false, true)  => { '∨' }
+                (false, false) => { '∧' }
+            }
+        }
     }
 }
 
-fn flip_colors(frame: &mut IBasic, pos: Coord) {
+pub(super) fn flip_colors(frame: &mut IBasic, pos: Coord) {
     if let Pixel::Opaque(data) = frame.get_pixel(pos) {
         frame.set_colors(pos, ColorSet { fg: data.bg, bg: data.fg })
     }
 }
 
-fn to_range(pos: i32, min: i32, max: i32) -> i32 {
+pub(super) fn to_range(pos: i32, min: i32, max: i32) -> i32 {
     if pos < min {
         return min - pos
     }
@@ -229,4 +430,56 @@ fn to_range(pos: i32, min: i32, max: i32) -> i32 {
     }
 
     0
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_skips_gaps_without_lines() {
+        let (lines, nodes) = layout(&[true, false, true, false, true]);
+
+        assert_eq!(lines, vec![0, 2, 3, 5, 6]);
+        assert_eq!(nodes, vec![1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn block_and_cage_edges() {
+        let blocks = Region::block_map(Coord { x: 2, y: 2 });
+
+        assert!(edge_left(&blocks, Coord { x: 0, y: 0 }));
+        assert!(!edge_left(&blocks, Coord { x: 1, y: 0 }));
+        assert!(edge_left(&blocks, Coord { x: 2, y: 1 }));
+        assert!(edge_above(&blocks, Coord { x: 0, y: 2 }));
+        assert!(!edge_above(&blocks, Coord { x: 3, y: 1 }));
+        assert!(edge_above(&blocks, Coord { x: 3, y: 4 }));
+
+        let map = cage_map(&[Cage::new(3, vec![Coord { x: 1, y: 1 }, Coord { x: 2, y: 1 }])], 4);
+
+        assert!(cage_left(&map, Coord { x: 1, y: 1 }));
+        assert!(!cage_left(&map, Coord { x: 2, y: 1 }));
+        assert!(cage_left(&map, Coord { x: 3, y: 1 }));
+        assert!(cage_above(&map, Coord { x: 1, y: 2 }));
+        assert!(!cage_above(&map, Coord { x: 0, y: 1 }));
+    }
+
+    #[test]
+    fn edge_chars() {
+        let a = Coord { x: 1, y: 1 };
+        let right = Coord { x: 2, y: 1 };
+        let below = Coord { x: 1, y: 2 };
+
+        assert_eq!(edge_char(&Edge::new(Mark::White, a, right)), '○');
+        assert_eq!(edge_char(&Edge::new(Mark::Greater, a, right)), '>');
+        assert_eq!(edge_char(&Edge::new(Mark::Greater, right, a)), '<');
+        assert_eq!(edge_char(&Edge::new(Mark::Greater, a, below)), '∨');
+        assert_eq!(edge_char(&Edge::new(Mark::Greater, below, a)), '∧');
+    }
+
+    #[test]
+    fn shift_into_range() {
+        assert_eq!(to_range(0, 2, 10), 2);
+        assert_eq!(to_range(5, 2, 10), 0);
+        assert_eq!(to_range(14, 2, 10), -4);
+    }
+}
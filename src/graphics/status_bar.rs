@@ -4,7 +4,8 @@ use frames::layout_manager::*;
 use frames::ManagerTrait;
 use frames::frame_types::text;
 
-use crate::state::State;
+use crate::state::{State, MultiState};
+use crate::rating::Rating;
 
 struct StatusBarUpdate {}
 
@@ -15,8 +16,9 @@ impl position::SizeUpdate for StatusBarUpdate {
 }
 
 pub struct StatusBar {
-    frame: text::Text,
-    pos:   position::Position,
+    frame:  text::Text,
+    pos:    position::Position,
+    rating: String,
 }
 
 impl StatusBar {
@@ -44,6 +46,7 @@ impl StatusBar {
         Self {
             frame,
             pos,
+            rating: String::new(),
         }
     }
     
@@ -51,12 +54,33 @@ impl StatusBar {
         self.pos.borrow_mut().data.enabled = enabled;
     }
 
+    ///Shown from the next update.
+    pub fn set_rating(&mut self, rating: &Rating) {
+        self.rating = format!("{} ({})", rating.label(), rating.score());
+    }
+
+    pub fn set_unrated(&mut self) {
+        self.rating = String::from("not rated");
+    }
+
     pub fn update(&mut self, state: &State) {
         self.frame.borrow_mut().entries[0].set_text(
-            format!{"Guess Level {} | Completion {} / {}",
+            format!{"Guess Level {} | Completion {} / {} | Difficulty {}",
             state.guess_level(),
             state.finished(),
             state.total(),
+            self.rating,
+        })
+    }
+
+    pub fn update_multi(&mut self, multi: &MultiState) {
+        self.frame.borrow_mut().entries[0].set_text(
+            format!{"{} | Grids {} | Guess Level {} | Completion {} / {}",
+            multi.shape().name(),
+            multi.grids().len(),
+            multi.guess_level(),
+            multi.finished(),
+            multi.total(),
         })
     }
 }
@@ -0,0 +1,158 @@
+use crate::prelude::*;
+use crate::state::{State, Unit};
+use crate::rating::Technique;
+use crate::graphics::num_char;
+
+///The simplest next step towards solving the puzzle, along with the nodes that explain it.
+pub struct Hint {
+    pub technique: Technique,
+    pub pos:       Coord,
+    pub num:       usize,
+    ///The unit the number has to go in, for hidden singles.
+    pub unit:      Option<Unit>,
+    ///The nodes to point out, including pos.
+    pub cells:     Vec<Coord>,
+}
+
+impl Hint {
+    ///Looks for naked singles first and then hidden singles. Returns None if neither can make progress.
+    pub fn find(state: &State) -> Option<Self> {
+        if state.has_conflicts() { return None }
+
+        naked_single(state).or_else(|| hidden_single(state))
+    }
+
+    pub fn description(&self) -> String {
+        let num = num_char(self.num);
+        let pos = name(self.pos);
+
+        match (self.technique, self.unit) {
+            (Technique::HiddenSingle, Some(unit)) => {
+                format!("Hidden single: {} can only go in {} within {}", num, pos, unit.name())
+            }
+            _ => {
+                format!("Naked single: {} can only be {}, every other number is ruled out for this cell", pos, num)
+            }
+        }
+    }
+
+    pub fn apply(&self, state: &mut State) {
+        state.set(self.pos, self.num);
+    }
+}
+
+///Writes a node the usual way for hints, e.g. r3c5 counting from 1.
+pub fn name(pos: Coord) -> String {
+    format!("r{}c{}", pos.y + 1, pos.x + 1)
+}
+
+fn naked_single(state: &State) -> Option<Hint> {
+    let nodes = state.nodes();
+
+    for pos in CoordIter::new(Coord { x: 0, y: 0 }, nodes.size()) {
+        if nodes.get(pos).num_possibilities() != Some(1) { continue; }
+
+        let num = nodes.get(pos).possibilities().unwrap().first().unwrap();
+
+        // one solved node for each of the other numbers shows why it was ruled out.
+        let mut cells = vec![pos];
+
+        for other in (0..state.size()).filter(|x| *x != num) {
+            let found = state.peers(pos)
+                .iter()
+                .find(|x| nodes.get(**x).get_num() == Some(other));
+
+            if let Some(cell) = found {
+                if !cells.contains(cell) { cells.push(*cell); }
+            }
+        }
+
+        return Some(Hint {
+            technique: Technique::NakedSingle,
+            pos,
+            num,
+            unit: None,
+            cells,
+        })
+    }
+
+    None
+}
+
+fn hidden_single(state: &State) -> Option<Hint> {
+    let nodes = state.nodes();
+
+    // a cage doesn't need every number, so a number with one place left in it can still go elsewhere.
+    for region in state.regions().iter().filter(|x| x.is_full(state.size())) {
+        for num in 0..state.size() {
+            let mut options = region.cells.iter().copied().filter(|x| {
+                nodes.get(*x).possibilities().is_some_and(|list| list[num])
+            });
+
+            if let (Some(pos), None) = (options.next(), options.next()) {
+                let mut cells = vec![pos];
+                cells.extend(region.cells.iter().copied().filter(|x| *x != pos));
+
+                return Some(Hint {
+                    technique: Technique::HiddenSingle,
+                    pos,
+                    num,
+                    unit: Some(region.unit),
+                    cells,
+                })
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn naked_single() {
+        let mut state = State::new(Coord { x: 2, y: 2 });
+        state.set(Coord { x: 0, y: 0 }, 0);
+        state.set(Coord { x: 1, y: 0 }, 1);
+        state.set(Coord { x: 3, y: 1 }, 2);
+
+        let hint = Hint::find(&state).unwrap();
+
+        assert_eq!(hint.technique, Technique::NakedSingle);
+        assert_eq!((hint.pos.y, hint.num), (0, 3));
+        assert_eq!(hint.cells.len(), 4);
+        assert_eq!(hint.description(), format!("Naked single: {} can only be 4, every other number is ruled out for this cell", name(hint.pos)));
+    }
+
+    #[test]
+    fn hidden_single() {
+        let mut state = State::new(Coord { x: 2, y: 2 });
+        state.set(Coord { x: 0, y: 0 }, 0);
+        state.set(Coord { x: 2, y: 2 }, 0);
+
+        let hint = Hint::find(&state).unwrap();
+
+        assert_eq!(hint.technique, Technique::HiddenSingle);
+        assert!([Coord { x: 3, y: 1 }, Coord { x: 1, y: 3 }].contains(&hint.pos));
+        assert_eq!(hint.num, 0);
+        assert_eq!(hint.cells[0], hint.pos);
+        assert!(hint.description().starts_with("Hidden single"));
+
+        hint.apply(&mut state);
+        assert!(!state.has_conflicts());
+        assert_eq!(state.finished(), 3);
+    }
+
+    #[test]
+    fn no_hint() {
+        let mut state = State::new(Coord { x: 2, y: 2 });
+        assert!(Hint::find(&state).is_none());
+
+        // nothing is hinted once the puzzle has gone wrong.
+        state.set(Coord { x: 0, y: 0 }, 0);
+        state.set(Coord { x: 1, y: 0 }, 0);
+        assert!(Hint::find(&state).is_none());
+    }
+}
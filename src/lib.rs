@@ -2,3 +2,11 @@ pub mod state;
 pub mod graphics;
 pub mod prelude;
 pub mod game;
+pub mod multi_game;
+pub mod formats;
+pub mod cli;
+pub mod random;
+pub mod generator;
+pub mod rating;
+pub mod hint;
+pub mod dlx;
@@ -6,10 +6,55 @@ use frames::crossterm;
 use crossterm::terminal::{enable_raw_mode, disable_raw_mode};
 use crossterm::event::KeyCode;
 
-use sudoku::graphics::{SmallGrid, List};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use sudoku::graphics::{Grid, SmallGrid, List, PopUp};
 use sudoku::game::Game;
+use sudoku::multi_game::MultiGame;
+use sudoku::formats::{Puzzle, load_session, load_multi};
+use sudoku::state::{State, Chess, MultiState, Shape};
+use sudoku::cli;
+use sudoku::generator::{Generator, Symmetry};
+use sudoku::random::Random;
+
+const USAGE: &str = "Usage:
+    sudoku                  Choose a size and enter the puzzle by hand.
+    sudoku FILE             Load the puzzle from FILE, or resume it if FILE is a .session file.
+    sudoku --puzzle TEXT    Load the puzzle written in TEXT, e.g. \"53..7....6..195...\"
+    sudoku --multi SHAPE    Enter a samurai or twin puzzle by hand, saved to SHAPE.samurai.
+                            Multi-grid puzzles are loaded from .samurai files.
+    sudoku solve ...        Solve puzzles without the interface, see \"sudoku solve --help\".
+    sudoku generate ...     Make new puzzles, see \"sudoku generate --help\".";
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.first().map(|x| x.as_str()) {
+        Some("solve")    => { exit(cli::solve(&args[1..])) }
+        Some("generate") => { exit(cli::generate(&args[1..])) }
+        _ => {}
+    }
+
+    let multi = match read_multi_args(&args) {
+        Ok(multi) => multi,
+        Err(msg) => {
+            eprintln!("{}\n\n{}", msg, USAGE);
+            exit(1);
+        }
+    };
+
+    let (state, session) = match multi {
+        Some(_) => { (None, None) }
+        None => match read_args(args) {
+            Ok(args) => args,
+            Err(msg) => {
+                eprintln!("{}\n\n{}", msg, USAGE);
+                exit(1);
+            }
+        }
+    };
+
     enable_raw_mode().unwrap();
 
     let mut manager = LayoutManager::new().unwrap();
@@ -20,43 +65,199 @@ fn main() {
         pos: position::craft().update(position::update_types::MatchSize{}).done()
     });
 
-    let mut grid = SmallGrid::new(&mut manager, Coord { x: 3, y: 3 });
+    if let Some((multi, path)) = multi {
+        MultiGame::new(&mut manager, multi, &path).main(&mut manager);
+
+        disable_raw_mode().unwrap();
+        return
+    }
+
+    let sub_size = state.as_ref().map_or(Coord { x: 3, y: 3 }, |x| x.sub_size());
+
+    let mut grid = SmallGrid::new(&mut manager, sub_size);
     let mut list = List::new(&mut manager);
-    list.show_size(Coord { x: 3, y: 3 });
+    list.show_size(sub_size, Symmetry::Rotational, false, false, &[]);
 
-    set_size(&mut manager, &mut grid, &mut list);
+    let state = match state {
+        Some(state) => { Some(state) }
+        None => {
+            let mut popup = PopUp::new(&mut manager);
+            set_size(&mut manager, &mut grid, &mut list, &mut popup)
+        }
+    };
     
     let mut game = Game::new(&mut manager, grid, list);
+
+    if let Some(state) = state {
+        game.set_state(state);
+    }
+
+    if let Some(session) = session {
+        game.set_session(&session);
+    }
     
     game.main(&mut manager);
 
     disable_raw_mode().unwrap();
 }
 
-pub fn set_size(manager: &mut LayoutManager, grid: &mut SmallGrid, list: &mut List) {
+///Returns the puzzle to start with and the session file to save it to if they were given.
+fn read_args(args: Vec<String>) -> Result<(Option<State>, Option<PathBuf>), String> {
+    let mut args = args.into_iter();
+
+    let (state, session) = match args.next().as_deref() {
+        None => { return Ok((None, None)) }
+        Some("-h") | Some("--help") => {
+            println!("{}", USAGE);
+            exit(0);
+        }
+        Some("--puzzle") => {
+            let text = args.next().ok_or("--puzzle needs the puzzle text")?;
+            let puzzle = Puzzle::parse(&text).map_err(|err| format!("Invalid puzzle: {}", err))?;
+
+            (puzzle.to_state(), None)
+        }
+        Some(path) => {
+            let path = Path::new(path);
+            let error = |err| format!("Could not load \"{}\": {}", path.display(), err);
+
+            if path.extension().is_some_and(|x| x == "session") {
+                (load_session(path).map_err(error)?, Some(path.to_path_buf()))
+            }
+            else {
+                (Puzzle::load(path).map_err(error)?.to_state(), Some(path.with_extension("session")))
+            }
+        }
+    };
+
+    if let Some(arg) = args.next() {
+        return Err(format!("Unexpected argument \"{}\"", arg))
+    }
+
+    Ok((Some(state), session))
+}
+
+///Returns the multi-grid puzzle to play and the file to save it to, if one was asked for.
+fn read_multi_args(args: &[String]) -> Result<Option<(MultiState, PathBuf)>, String> {
+    let multi = match args.first().map(|x| x.as_str()) {
+        Some("--multi") => {
+            let name = args.get(1).ok_or("--multi needs a shape")?;
+            let shape = Shape::parse(name).ok_or(format!("Unknown shape \"{}\"", name))?;
+
+            (MultiState::new(shape, Coord { x: 3, y: 3 }), PathBuf::from(format!("{}.samurai", shape.name())), 2)
+        }
+        Some(path) if Path::new(path).extension().is_some_and(|x| x == "samurai") => {
+            let path = Path::new(path);
+            let multi = load_multi(path).map_err(|err| format!("Could not load \"{}\": {}", path.display(), err))?;
+
+            (multi, path.to_path_buf(), 1)
+        }
+        _ => { return Ok(None) }
+    };
+
+    let (multi, path, used) = multi;
+
+    if let Some(arg) = args.get(used) {
+        return Err(format!("Unexpected argument \"{}\"", arg))
+    }
+
+    Ok(Some((multi, path)))
+}
+
+///Returns a generated puzzle if one was asked for, otherwise the puzzle is entered by hand.
+///Enter gives an empty puzzle so the diagonals, windows and chess constraints can be kept.
+pub fn set_size(manager: &mut LayoutManager, grid: &mut SmallGrid, list: &mut List, popup: &mut PopUp) -> Option<State> {
+    let mut symmetry = Symmetry::Rotational;
+    let mut diagonal = false;
+    let mut windows = false;
+    let mut chess = Vec::new();
+
     loop {
         manager.draw().unwrap();
 
         if let Input::KeyBoard(x) = manager.get_input() {
             match x.code {
-                KeyCode::Esc   => { break; },
-                KeyCode::Enter => { break; },
-                KeyCode::Left  => { change_size(grid, list, Coord { x:  0, y: -1 }) },
-                KeyCode::Right => { change_size(grid, list, Coord { x:  0, y:  1 }) },
-                KeyCode::Up    => { change_size(grid, list, Coord { x: -1, y:  0 }) },
-                KeyCode::Down  => { change_size(grid, list, Coord { x:  1, y:  0 }) },
+                KeyCode::Esc   => { return None },
+                KeyCode::Enter => {
+                    let mut puzzle = Puzzle::new(grid.get_size());
+                    puzzle.diagonal = diagonal;
+                    puzzle.windows = windows;
+                    puzzle.chess = chess;
+
+                    return Some(puzzle.to_state())
+                },
+                KeyCode::Left  => { change_size(grid, list, symmetry, &mut diagonal, &mut windows, &chess, Coord { x:  0, y: -1 }) },
+                KeyCode::Right => { change_size(grid, list, symmetry, &mut diagonal, &mut windows, &chess, Coord { x:  0, y:  1 }) },
+                KeyCode::Up    => { change_size(grid, list, symmetry, &mut diagonal, &mut windows, &chess, Coord { x: -1, y:  0 }) },
+                KeyCode::Down  => { change_size(grid, list, symmetry, &mut diagonal, &mut windows, &chess, Coord { x:  1, y:  0 }) },
+                KeyCode::Char('s') | KeyCode::Char('S') => {
+                    symmetry = symmetry.next();
+                    list.show_size(grid.get_size(), symmetry, diagonal, windows, &chess);
+                }
+                KeyCode::Char('d') | KeyCode::Char('D') => {
+                    let size = grid.get_size();
+
+                    // the diagonals only line up with the blocks when they are square.
+                    if size.x == size.y {
+                        diagonal = !diagonal;
+                        list.show_size(size, symmetry, diagonal, windows, &chess);
+                    }
+                }
+                KeyCode::Char('w') | KeyCode::Char('W') => {
+                    let size = grid.get_size();
+
+                    if size.x == size.y {
+                        windows = !windows;
+                        list.show_size(size, symmetry, diagonal, windows, &chess);
+                    }
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') => {
+                    toggle_chess(&mut chess, Chess::Knight);
+                    list.show_size(grid.get_size(), symmetry, diagonal, windows, &chess);
+                }
+                KeyCode::Char('k') | KeyCode::Char('K') => {
+                    toggle_chess(&mut chess, Chess::King);
+                    list.show_size(grid.get_size(), symmetry, diagonal, windows, &chess);
+                }
+                KeyCode::Char('g') | KeyCode::Char('G') => {
+                    let mut generator = Generator::new(Random::seed_from_time(), symmetry);
+                    generator.set_diagonal(diagonal);
+                    generator.set_windows(windows);
+                    generator.set_chess(&chess);
+
+                    if let Some(puzzle) = generator.generate(grid.get_size()) {
+                        return Some(puzzle.to_state())
+                    }
+
+                    popup.message("No grid fits these constraints.");
+                    manager.draw().unwrap();
+                    manager.get_input();
+                    popup.disable();
+                }
                 _ => {}
             }
         }
     }
 }
 
-fn change_size(grid: &mut SmallGrid, list: &mut List, change: Coord) {
+fn change_size(grid: &mut SmallGrid, list: &mut List, symmetry: Symmetry, diagonal: &mut bool, windows: &mut bool, chess: &[Chess], change: Coord) {
     let new = grid.get_size() + change;
 
     let check = new.x * new.y;
     if 0 < check && check <= 25  {
+        *diagonal &= new.x == new.y;
+        *windows &= new.x == new.y;
+
         grid.resize(new);
-        list.show_size(new);
+        list.show_size(new, symmetry, *diagonal, *windows, chess);
+    }
+}
+
+fn toggle_chess(chess: &mut Vec<Chess>, toggle: Chess) {
+    if chess.contains(&toggle) {
+        chess.retain(|x| *x != toggle);
+    }
+    else {
+        chess.push(toggle);
     }
 }
@@ -0,0 +1,172 @@
+use crate::prelude::*;
+use crate::graphics::{MultiGrid, List, StatusBar, PopUp, char_num};
+use crate::state::{MultiState, History};
+use crate::formats::save_multi;
+
+use std::path::{Path, PathBuf};
+
+use frames::{prelude::*, ManagerTrait};
+use frames::layout_manager::LayoutManager;
+use frames::crossterm::event::{KeyCode, KeyModifiers};
+
+///Plays a multi-grid puzzle such as a Samurai. Numbers are entered straight away, the pointer
+///moves over the whole puzzle and the solvers work on every grid at once. Numbers set at guess
+///level 0 are the givens, above it they are the player's entries and can't replace a given.
+///The commands are on Ctrl so every letter can be typed as a number.
+pub struct MultiGame {
+    grid:    MultiGrid,
+    status:  StatusBar,
+    list:    List,
+    popup:   PopUp,
+    state:   MultiState,
+    history: History<MultiState>,
+    path:    PathBuf,
+}
+
+impl MultiGame {
+    pub fn new(manager: &mut LayoutManager, state: MultiState, path: &Path) -> Self {
+        let mut grid = MultiGrid::new(manager, &state);
+        grid.update(&state);
+
+        Self {
+            status:  StatusBar::new(manager),
+            list:    List::new(manager),
+            popup:   PopUp::new(manager),
+            history: History::new(),
+            path:    path.to_path_buf(),
+            grid,
+            state,
+        }
+    }
+
+    pub fn main(&mut self, manager: &mut LayoutManager) {
+        self.list.multi();
+        self.grid.pointer_on(manager.size());
+        self.update();
+
+        loop {
+            manager.draw().unwrap();
+
+            if let Input::KeyBoard(x) = manager.get_input() {
+                use KeyCode::*;
+                match x {
+                    KeyEvent{code: Esc, ..} => {
+                        self.popup.leaving();
+                        manager.draw().unwrap();
+                        if self.exit(manager) {
+                            break;
+                        }
+                        self.popup.disable();
+                    }
+                    KeyEvent{code: Left, modifiers: KeyModifiers::SHIFT, ..}  => { self.grid.move_by(Coord { x: -1, y:  0 }) }
+                    KeyEvent{code: Right, modifiers: KeyModifiers::SHIFT, ..} => { self.grid.move_by(Coord { x:  1, y:  0 }) }
+                    KeyEvent{code: Up, modifiers: KeyModifiers::SHIFT, ..}    => { self.grid.move_by(Coord { x:  0, y: -1 }) }
+                    KeyEvent{code: Down, modifiers: KeyModifiers::SHIFT, ..}  => { self.grid.move_by(Coord { x:  0, y:  1 }) }
+                    KeyEvent{code: Left, ..}  => { self.grid.inc_pointer(&self.state, Coord { x: -1, y:  0 }, manager.size()) }
+                    KeyEvent{code: Right, ..} => { self.grid.inc_pointer(&self.state, Coord { x:  1, y:  0 }, manager.size()) }
+                    KeyEvent{code: Up, ..}    => { self.grid.inc_pointer(&self.state, Coord { x:  0, y: -1 }, manager.size()) }
+                    KeyEvent{code: Down, ..}  => { self.grid.inc_pointer(&self.state, Coord { x:  0, y:  1 }, manager.size()) }
+                    KeyEvent{code: Backspace, ..} => {
+                        let pos = self.grid.pointer();
+
+                        if self.can_change(pos) {
+                            self.history.change(&mut self.state, |x| x.clear(pos));
+                            self.update();
+                        }
+                    }
+                    KeyEvent{code: Char(c), modifiers: KeyModifiers::CONTROL, ..} => {
+                        match c {
+                            'z' => {
+                                self.history.undo(&mut self.state);
+                                self.update();
+                            }
+                            'y' => {
+                                self.history.redo(&mut self.state);
+                                self.update();
+                            }
+                            'f' => {
+                                self.history.change(&mut self.state, |x| x.full_solve_no_guessing());
+                                self.update();
+                            }
+                            'g' => {
+                                if !self.history.change(&mut self.state, |x| x.full_solve()) {
+                                    self.popup.message("No solution found.");
+                                    self.wait_popup(manager);
+                                }
+
+                                self.update();
+                            }
+                            's' => {
+                                self.save(manager);
+                            }
+                            _ => {}
+                        }
+                    }
+                    KeyEvent{code: Char(c), ..} => {
+                        match c {
+                            '-' => {
+                                self.history.change(&mut self.state, |x| x.dec_guess());
+                                self.update();
+                            }
+                            '=' => {
+                                self.history.change(&mut self.state, |x| x.inc_guess());
+                                self.update();
+                            }
+                            _ => {
+                                let pos = self.grid.pointer();
+                                let size = self.state.grids()[0].size();
+
+                                if let Some(num) = char_num(c).filter(|x| *x < size) {
+                                    if self.can_change(pos) {
+                                        self.history.change(&mut self.state, |x| x.set(pos, num));
+                                        self.update();
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn exit(&mut self, manager: &mut LayoutManager) -> bool {
+        loop {
+            manager.draw().unwrap();
+
+            if let Input::KeyBoard(x) = manager.get_input() {
+                match x.code {
+                    KeyCode::Esc   => { return true; }
+                    KeyCode::Enter => { return false; }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn save(&mut self, manager: &mut LayoutManager) {
+        match save_multi(&self.state, &self.path) {
+            Ok(()) => { self.popup.message("Puzzle saved.") }
+            Err(err) => { self.popup.message(&format!("Could not save: {}", err)) }
+        }
+
+        self.wait_popup(manager);
+    }
+
+    fn wait_popup(&mut self, manager: &mut LayoutManager) {
+        manager.draw().unwrap();
+        manager.get_input();
+        self.popup.disable();
+    }
+
+    ///Givens can only be changed at guess level 0.
+    fn can_change(&self, pos: Coord) -> bool {
+        self.state.guess_level() == 0 || !self.state.is_given(pos)
+    }
+
+    fn update(&mut self) {
+        self.grid.update(&self.state);
+        self.status.update_multi(&self.state);
+    }
+}
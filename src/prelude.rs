@@ -1,6 +1,7 @@
 pub use frames::prelude::Coord;
 use std::iter::Iterator;
 
+#[derive(Clone, PartialEq)]
 pub struct Vec2D<T> {
     buf: Vec<T>,
     size: Coord,
@@ -0,0 +1,250 @@
+use crate::formats::Puzzle;
+use crate::state::State;
+
+///The ways a node can be solved, from weakest to strongest.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Technique {
+    ///The only possibility left for a node, found by basic solve.
+    NakedSingle,
+    ///The only node in a row, column or block that can hold a number, found by exclusive solve.
+    HiddenSingle,
+    ///A number that can't fit a neighbour's numbers across a Kropki dot, X, V or greater-than sign.
+    EdgeMark,
+    ///A number that can't help a killer cage, or the innies of a region, make its sum.
+    CageSum,
+    ///A number locked into the part of a block shared with a row or column.
+    LockedCandidates,
+    ///Two nodes of a group that can only be the same two numbers.
+    NakedPair,
+    ///Two numbers that can only go in the same two nodes of a group.
+    HiddenPair,
+    NakedTriple,
+    HiddenTriple,
+    NakedQuad,
+    HiddenQuad,
+    ///A guess made by full solve.
+    Guess,
+}
+
+impl Technique {
+    pub const ALL: [Technique; 12] = [
+        Self::NakedSingle, Self::EdgeMark, Self::HiddenSingle, Self::CageSum, Self::LockedCandidates,
+        Self::NakedPair, Self::HiddenPair,
+        Self::NakedTriple, Self::HiddenTriple,
+        Self::NakedQuad, Self::HiddenQuad,
+        Self::Guess,
+    ];
+
+    ///The naked and hidden subset techniques for groups of two to four nodes, weakest first.
+    const SUBSETS: [(Technique, Technique, usize); 3] = [
+        (Self::NakedPair, Self::HiddenPair, 2),
+        (Self::NakedTriple, Self::HiddenTriple, 3),
+        (Self::NakedQuad, Self::HiddenQuad, 4),
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::NakedSingle      => { "naked single" }
+            Self::HiddenSingle     => { "hidden single" }
+            Self::EdgeMark         => { "edge mark" }
+            Self::CageSum          => { "cage sum" }
+            Self::LockedCandidates => { "locked candidates" }
+            Self::NakedPair        => { "naked pair" }
+            Self::HiddenPair       => { "hidden pair" }
+            Self::NakedTriple      => { "naked triple" }
+            Self::HiddenTriple     => { "hidden triple" }
+            Self::NakedQuad        => { "naked quad" }
+            Self::HiddenQuad       => { "hidden quad" }
+            Self::Guess            => { "guess" }
+        }
+    }
+
+    ///How much each use adds to the score.
+    pub fn weight(&self) -> usize {
+        match self {
+            Self::NakedSingle      => { 1 }
+            Self::HiddenSingle     => { 3 }
+            Self::EdgeMark         => { 2 }
+            Self::CageSum          => { 4 }
+            Self::LockedCandidates => { 5 }
+            Self::NakedPair        => { 6 }
+            Self::HiddenPair       => { 8 }
+            Self::NakedTriple      => { 10 }
+            Self::HiddenTriple     => { 12 }
+            Self::NakedQuad        => { 14 }
+            Self::HiddenQuad       => { 16 }
+            Self::Guess            => { 20 }
+        }
+    }
+
+    ///The easiest difficulty of a puzzle that needs this technique.
+    pub fn difficulty(&self) -> Difficulty {
+        match self {
+            Self::NakedSingle      => { Difficulty::Easy }
+            Self::HiddenSingle     => { Difficulty::Medium }
+            Self::EdgeMark         => { Difficulty::Easy }
+            Self::CageSum          => { Difficulty::Medium }
+            Self::LockedCandidates => { Difficulty::Hard }
+            Self::NakedPair        => { Difficulty::Hard }
+            Self::HiddenPair       => { Difficulty::Hard }
+            Self::NakedTriple      => { Difficulty::Expert }
+            Self::HiddenTriple     => { Difficulty::Expert }
+            Self::NakedQuad        => { Difficulty::Expert }
+            Self::HiddenQuad       => { Difficulty::Expert }
+            Self::Guess            => { Difficulty::NeedsGuessing }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+    NeedsGuessing,
+}
+
+impl Difficulty {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "easy"           => { Some(Self::Easy) }
+            "medium"         => { Some(Self::Medium) }
+            "hard"           => { Some(Self::Hard) }
+            "expert"         => { Some(Self::Expert) }
+            "needs-guessing" => { Some(Self::NeedsGuessing) }
+            _ => { None }
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Easy          => { "easy" }
+            Self::Medium        => { "medium" }
+            Self::Hard          => { "hard" }
+            Self::Expert        => { "expert" }
+            Self::NeedsGuessing => { "needs guessing" }
+        }
+    }
+}
+
+///The techniques needed to solve a puzzle and how often each was used.
+pub struct Rating {
+    pub counts: Vec<(Technique, usize)>,
+    pub solved: bool,
+}
+
+impl Rating {
+    ///Solves the givens of the puzzle, using the weakest technique that makes progress at each step.
+    pub fn new(puzzle: &Puzzle) -> Self {
+        let mut state = puzzle.to_state();
+        let mut counts: Vec<(Technique, usize)> = Technique::ALL.iter().map(|x| (*x, 0)).collect();
+
+        let solved = loop {
+            if state.has_conflicts() { break false; }
+            if state.is_done() { break true; }
+
+            let before = state.finished();
+
+            let technique = if state.solve_basic() {
+                Technique::NakedSingle
+            }
+            // these only remove possibilities, so count each use rather than nodes found.
+            else if state.solve_edges() {
+                add(&mut counts, Technique::EdgeMark, 1);
+                continue;
+            }
+            else if state.solve_exclusive() {
+                Technique::HiddenSingle
+            }
+            else if state.solve_cages() {
+                add(&mut counts, Technique::CageSum, 1);
+                continue;
+            }
+            else if state.solve_intersections() {
+                add(&mut counts, Technique::LockedCandidates, 1);
+                continue;
+            }
+            else if let Some(technique) = solve_subset(&mut state) {
+                add(&mut counts, technique, 1);
+                continue;
+            }
+            else {
+                let solved = state.full_solve();
+                add(&mut counts, Technique::Guess, state.guess_count());
+                break solved;
+            };
+
+            add(&mut counts, technique, state.finished() - before);
+        };
+
+        Self {
+            counts,
+            solved,
+        }
+    }
+
+    pub fn of_state(state: &State) -> Self {
+        Self::new(&Puzzle::from_state(state))
+    }
+
+    pub fn count(&self, technique: Technique) -> usize {
+        self.counts.iter().find(|x| x.0 == technique).map_or(0, |x| x.1)
+    }
+
+    pub fn score(&self) -> usize {
+        self.counts.iter().map(|(technique, count)| technique.weight() * count).sum()
+    }
+
+    ///The difficulty of the hardest technique that was needed, None if the puzzle can't be solved.
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        if !self.solved { return None }
+
+        Some(self.counts.iter()
+            .filter(|x| x.1 > 0)
+            .map(|x| x.0.difficulty())
+            .fold(Difficulty::Easy, |a, b| if b > a { b } else { a }))
+    }
+
+    pub fn label(&self) -> &'static str {
+        self.difficulty().map_or("unsolvable", |x| x.name())
+    }
+}
+
+///Tries the weakest subset technique that removes a possibility.
+fn solve_subset(state: &mut State) -> Option<Technique> {
+    for (naked, hidden, size) in Technique::SUBSETS {
+        if state.solve_naked_subsets(size) { return Some(naked) }
+        if state.solve_hidden_subsets(size) { return Some(hidden) }
+    }
+
+    None
+}
+
+fn add(counts: &mut [(Technique, usize)], technique: Technique, amount: usize) {
+    if let Some(count) = counts.iter_mut().find(|x| x.0 == technique) {
+        count.1 += amount;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn difficulties() {
+        let easy = Puzzle::parse("278.49....19..5.....61......2....918.........697....3......16.....9..25....82.147").unwrap();
+        let hard = Puzzle::parse("8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..").unwrap();
+        let broken = Puzzle::parse("55..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79").unwrap();
+
+        let rating = Rating::new(&easy);
+        assert_eq!(rating.difficulty(), Some(Difficulty::Easy));
+        assert_eq!(rating.count(Technique::NakedSingle), 81 - 28);
+
+        let rating = Rating::new(&hard);
+        assert_eq!(rating.difficulty(), Some(Difficulty::NeedsGuessing));
+        assert!(rating.score() > 20);
+
+        assert_eq!(Rating::new(&broken).difficulty(), None);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::prelude::*;
+
+use super::Candidates;
+
+///Sets of numbers are only searched when there are at most this many ways to pick them,
+///large cages in large puzzles are left to guessing.
+const MAX_SETS: usize = 100_000;
+
+///A group of nodes whose numbers add up to a sum without repeating, as in killer sudoku.
+///The sum counts numbers from 1 the way they are shown.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cage {
+    pub sum:   usize,
+    pub cells: Vec<Coord>,
+}
+
+impl Cage {
+    pub fn new<T: IntoIterator<Item = Coord>>(sum: usize, cells: T) -> Self {
+        Self {
+            sum,
+            cells: cells.into_iter().collect(),
+        }
+    }
+
+    pub fn contains(&self, pos: Coord) -> bool {
+        self.cells.contains(&pos)
+    }
+
+    ///The top left node, where the sum is written.
+    pub fn corner(&self) -> Coord {
+        *self.cells.iter().min_by_key(|pos| (pos.y, pos.x)).expect("cages have at least one node")
+    }
+
+    ///True if the cage fits in a puzzle of the given size and its sum can be made.
+    pub fn is_valid(&self, size: usize) -> bool {
+        let (min, max) = sum_range(self.cells.len(), size);
+        !self.cells.is_empty() && self.cells.len() <= size && min <= self.sum && self.sum <= max
+    }
+
+    ///True if the cage repeats a node or shares one with any of the other cages. The sums of what
+    ///is left outside cages are only right when no node is in two of them.
+    pub fn overlaps(&self, cages: &[Cage]) -> bool {
+        self.cells.iter().enumerate().any(|(i, pos)| self.cells[..i].contains(pos) || cages.iter().any(|x| x.contains(*pos)))
+    }
+}
+
+///The smallest and largest sums of `count` different numbers from a puzzle of the given size.
+pub fn sum_range(count: usize, size: usize) -> (usize, usize) {
+    let min = count * (count + 1) / 2;
+    let max = count * size - count * count.saturating_sub(1) / 2;
+
+    (min, max)
+}
+
+///Which of their possibilities nodes can still be, if their numbers have to add up to `sum`
+///without repeating. Every set of different numbers with the right sum is tried, and a number is
+///kept for a node if some set holds it that each node and each number of the set can fit into.
+///Returns None if there are too many sets to search.
+pub fn sum_options(options: &[Candidates], sum: usize, size: usize) -> Option<Vec<Candidates>> {
+    if ways(size, options.len()) > MAX_SETS { return None }
+
+    let mut sets = Vec::new();
+    find_sets(sum, options.len(), 0, size, Candidates::empty(), &mut sets);
+
+    let mut allowed = vec![Candidates::empty(); options.len()];
+
+    for set in sets {
+        if options.iter().any(|x| x.intersection(set).is_empty()) { continue; }
+        if set.iter().any(|num| !options.iter().any(|x| x.has(num))) { continue; }
+
+        for (allowed, option) in allowed.iter_mut().zip(options) {
+            *allowed = allowed.union(option.intersection(set));
+        }
+    }
+
+    Some(allowed)
+}
+
+///Every set of `count` different numbers from `start` up that adds up to `sum`, numbers counting from 1.
+fn find_sets(sum: usize, count: usize, start: usize, size: usize, set: Candidates, sets: &mut Vec<Candidates>) {
+    if count == 0 {
+        if sum == 0 { sets.push(set); }
+        return
+    }
+
+    for num in start..size {
+        let value = num + 1;
+
+        // the rest are larger than this one, so stop once even the smallest choices are too much.
+        let smallest = count * value + count * (count - 1) / 2;
+        if smallest > sum { break; }
+
+        let (_, largest) = sum_range(count - 1, size);
+        if value + largest < sum { continue; }
+
+        let mut next = set;
+        next.insert(num);
+        find_sets(sum - value, count - 1, num + 1, size, next, sets);
+    }
+}
+
+///How many ways there are to pick `k` things out of `n`, capped just above the set limit.
+fn ways(n: usize, k: usize) -> usize {
+    let mut total: usize = 1;
+
+    for i in 0..k.min(n) {
+        total = total * (n - i) / (i + 1);
+        if total > MAX_SETS { break; }
+    }
+
+    total
+}
@@ -0,0 +1,173 @@
+use std::fmt::Debug;
+use std::ops::{BitAnd, BitOr, Not, Shl, Index};
+
+///Unsigned integers used to hold one bit per number.
+pub trait Word: Copy + PartialEq + Debug + BitAnd<Output = Self> + BitOr<Output = Self> + Not<Output = Self> + Shl<usize, Output = Self> {
+    const ZERO: Self;
+    const ONE:  Self;
+    const BITS: usize;
+
+    fn count_ones(self) -> u32;
+    fn trailing_zeros(self) -> u32;
+}
+
+macro_rules! word {
+    ($($t:ty),*) => {
+        $(
+            impl Word for $t {
+                const ZERO: Self = 0;
+                const ONE:  Self = 1;
+                const BITS: usize = <$t>::BITS as usize;
+
+                fn count_ones(self) -> u32 { <$t>::count_ones(self) }
+                fn trailing_zeros(self) -> u32 { <$t>::trailing_zeros(self) }
+            }
+        )*
+    };
+}
+
+word!(u32, u64, u128);
+
+///A set of numbers stored as bits, u32 covers every size up to 32 numbers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Candidates<T: Word = u32> {
+    bits: T,
+}
+
+impl<T: Word> Candidates<T> {
+    pub fn empty() -> Self {
+        Self { bits: T::ZERO }
+    }
+
+    ///Every number from 0 up to but not including size.
+    pub fn full(size: usize) -> Self {
+        assert!(size <= T::BITS, "Too many numbers for candidate bits.");
+
+        if size == T::BITS {
+            Self { bits: !T::ZERO }
+        }
+        else {
+            Self { bits: !(!T::ZERO << size) }
+        }
+    }
+
+    pub fn has(&self, num: usize) -> bool {
+        self.bits & (T::ONE << num) != T::ZERO
+    }
+
+    pub fn insert(&mut self, num: usize) {
+        self.bits = self.bits | (T::ONE << num);
+    }
+
+    ///Returns true if the number was in the set.
+    pub fn remove(&mut self, num: usize) -> bool {
+        let had = self.has(num);
+        self.bits = self.bits & !(T::ONE << num);
+        had
+    }
+
+    pub fn toggle(&mut self, num: usize) {
+        if !self.remove(num) {
+            self.insert(num);
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.bits.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits == T::ZERO
+    }
+
+    ///The smallest number in the set.
+    pub fn first(&self) -> Option<usize> {
+        if self.is_empty() { None } else { Some(self.bits.trailing_zeros() as usize) }
+    }
+
+    ///The numbers in the set, smallest first.
+    pub fn iter(&self) -> Iter<T> {
+        Iter { bits: self.bits }
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self { bits: self.bits | other.bits }
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        Self { bits: self.bits & other.bits }
+    }
+
+    ///The numbers in this set that are not in the other.
+    pub fn difference(self, other: Self) -> Self {
+        Self { bits: self.bits & !other.bits }
+    }
+}
+
+impl<T: Word> Default for Candidates<T> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T: Word> Index<usize> for Candidates<T> {
+    type Output = bool;
+
+    fn index(&self, num: usize) -> &bool {
+        if self.has(num) { &true } else { &false }
+    }
+}
+
+impl<T: Word> FromIterator<usize> for Candidates<T> {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::empty();
+
+        for num in iter {
+            set.insert(num);
+        }
+
+        set
+    }
+}
+
+pub struct Iter<T: Word> {
+    bits: T,
+}
+
+impl<T: Word> Iterator for Iter<T> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.bits == T::ZERO { return None }
+
+        let num = self.bits.trailing_zeros() as usize;
+        self.bits = self.bits & !(T::ONE << num);
+
+        Some(num)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sets() {
+        let mut set: Candidates = Candidates::full(9);
+        assert_eq!(set.count(), 9);
+        assert!(set.remove(4));
+        assert!(!set.remove(4));
+        assert!(!set[4] && set[8] && !set[9]);
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![0, 1, 2, 3, 5, 6, 7, 8]);
+
+        let pair: Candidates = [2, 7].into_iter().collect();
+        assert_eq!(set.difference(pair).count(), 6);
+        assert_eq!(set.intersection(pair), pair);
+        assert_eq!(Candidates::<u32>::empty().first(), None);
+
+        let wide: Candidates<u128> = Candidates::full(100);
+        assert_eq!(wide.count(), 100);
+        assert_eq!(wide.iter().last(), Some(99));
+        assert_eq!(Candidates::<u32>::full(32).count(), 32);
+    }
+}
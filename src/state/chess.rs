@@ -0,0 +1,52 @@
+use crate::prelude::*;
+
+///Chess moves that can't join two nodes holding the same number, for anti-knight and anti-king puzzles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Chess {
+    Knight,
+    King,
+}
+
+impl Chess {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "anti-knight" => { Some(Self::Knight) }
+            "anti-king"   => { Some(Self::King) }
+            _ => { None }
+        }
+    }
+
+    ///Name of the constraint, also used when saving it.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Knight => { "anti-knight" }
+            Self::King   => { "anti-king" }
+        }
+    }
+
+    ///Name of a single move, for explaining conflicts.
+    pub fn move_name(&self) -> &'static str {
+        match self {
+            Self::Knight => { "knight's move" }
+            Self::King   => { "king's move" }
+        }
+    }
+
+    ///True if one move takes the piece from one node to the other.
+    pub fn joins(&self, a: Coord, b: Coord) -> bool {
+        let (x, y) = ((a.x - b.x).abs(), (a.y - b.y).abs());
+
+        match self {
+            Self::Knight => { (x, y) == (1, 2) || (x, y) == (2, 1) }
+            Self::King   => { x.max(y) == 1 }
+        }
+    }
+
+    ///Every node one move away, in a grid with the given number of nodes along each side.
+    pub fn moves(&self, pos: Coord, size: i32) -> Vec<Coord> {
+        let start = Coord { x: (pos.x - 2).max(0), y: (pos.y - 2).max(0) };
+        let end = Coord { x: (pos.x + 3).min(size), y: (pos.y + 3).min(size) };
+
+        CoordIter::new(start, end).filter(|x| self.joins(pos, *x)).collect()
+    }
+}
@@ -0,0 +1,133 @@
+use crate::prelude::*;
+
+///The marks that can sit on the edge between two neighbouring nodes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mark {
+    ///Black Kropki dot, one number is double the other.
+    Black,
+    ///White Kropki dot, the numbers are one apart.
+    White,
+    ///The numbers add up to 10.
+    X,
+    ///The numbers add up to 5.
+    V,
+    ///The number of the first node is larger than the second.
+    Greater,
+}
+
+impl Mark {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "black"   => { Some(Self::Black) }
+            "white"   => { Some(Self::White) }
+            "x"       => { Some(Self::X) }
+            "v"       => { Some(Self::V) }
+            "greater" => { Some(Self::Greater) }
+            _ => { None }
+        }
+    }
+
+    ///Name of the mark, also used when saving it.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Black   => { "black" }
+            Self::White   => { "white" }
+            Self::X       => { "x" }
+            Self::V       => { "v" }
+            Self::Greater => { "greater" }
+        }
+    }
+
+    ///Name of the mark for explaining conflicts.
+    pub fn mark_name(&self) -> &'static str {
+        match self {
+            Self::Black   => { "black dot" }
+            Self::White   => { "white dot" }
+            Self::X       => { "X" }
+            Self::V       => { "V" }
+            Self::Greater => { "greater-than sign" }
+        }
+    }
+
+    ///True if the mark holds between the numbers of the first and second node, counting from 0 like nodes do.
+    pub fn holds(&self, a: usize, b: usize) -> bool {
+        let (a, b) = (a + 1, b + 1);
+
+        match self {
+            Self::Black   => { a == 2 * b || b == 2 * a }
+            Self::White   => { a.abs_diff(b) == 1 }
+            Self::X       => { a + b == 10 }
+            Self::V       => { a + b == 5 }
+            Self::Greater => { a > b }
+        }
+    }
+
+    ///True if leaving the mark out can mean it doesn't hold. Every pair of neighbours is one way
+    ///round or the other, so greater-than signs can't be.
+    pub fn can_be_negative(&self) -> bool {
+        !matches!(self, Self::Greater)
+    }
+}
+
+///A mark on the edge between two neighbouring nodes, as in Kropki, XV and greater-than puzzles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Edge {
+    pub mark: Mark,
+    ///For greater-than signs, `a` is the node with the larger number.
+    pub a:    Coord,
+    pub b:    Coord,
+}
+
+impl Edge {
+    pub fn new(mark: Mark, a: Coord, b: Coord) -> Self {
+        Self {
+            mark,
+            a,
+            b,
+        }
+    }
+
+    ///True if the edge is between the two nodes, either way round.
+    pub fn joins(&self, a: Coord, b: Coord) -> bool {
+        (self.a == a && self.b == b) || (self.a == b && self.b == a)
+    }
+
+    ///True if the nodes are neighbours inside a puzzle with the given number of nodes along each side.
+    pub fn is_valid(&self, size: usize) -> bool {
+        let range = 0..size as i32;
+        let inside = |pos: Coord| range.contains(&pos.x) && range.contains(&pos.y);
+
+        inside(self.a) && inside(self.b) && neighbours(self.a, size as i32).contains(&self.b)
+    }
+
+    ///True if the numbers fit the mark, `num` being the number of the node at `pos` and `other` the number at the other end.
+    pub fn allows(&self, pos: Coord, num: usize, other: usize) -> bool {
+        if pos == self.a { self.mark.holds(num, other) } else { self.mark.holds(other, num) }
+    }
+}
+
+///The mark the numbers of two nodes break, and whether it is broken by being left out.
+///Negative marks are broken by numbers that would need the mark when there isn't one.
+pub fn broken_mark(edges: &[Edge], negative: &[Mark], a: Coord, num: usize, b: Coord, other: usize, size: i32) -> Option<(Mark, bool)> {
+    let joining: Vec<&Edge> = edges.iter().filter(|x| x.joins(a, b)).collect();
+
+    if let Some(edge) = joining.iter().find(|x| !x.allows(a, num, other)) {
+        return Some((edge.mark, false))
+    }
+
+    if negative.is_empty() || !neighbours(a, size).contains(&b) { return None }
+
+    negative.iter()
+        .filter(|mark| !joining.iter().any(|x| x.mark == **mark))
+        .find(|mark| mark.holds(num, other))
+        .map(|mark| (*mark, true))
+}
+
+///The nodes beside, above and below a node.
+pub fn neighbours(pos: Coord, size: i32) -> Vec<Coord> {
+    [Coord { x: 0, y: -1 }, Coord { x: -1, y: 0 }, Coord { x: 1, y: 0 }, Coord { x: 0, y: 1 }]
+        .into_iter()
+        .map(|x| pos + x)
+        .filter(|x| (0..size).contains(&x.x) && (0..size).contains(&x.y))
+        .collect()
+}
@@ -1,11 +1,13 @@
 use crate::prelude::*;
 
+#[derive(Clone)]
 pub struct Finished {
     current: usize,
     total:   usize,
     rows:    Vec<usize>,
     columns: Vec<usize>,
     blocks:  Vec2D<usize>,
+    block:   Coord,
 }
 
 impl Finished {
@@ -16,6 +18,7 @@ impl Finished {
             rows:    vec![0; size],
             columns: vec![0; size],
             blocks:  Vec2D::new(blocks, 0),
+            block:   Coord { x: blocks.y, y: blocks.x },
         }
     }
 
@@ -23,14 +26,14 @@ impl Finished {
         self.current += 1;
         self.rows[pos.y as usize] += 1;
         self.columns[pos.x as usize] += 1;
-        *self.blocks.get_mut(pos / self.blocks.size()) += 1;
+        *self.blocks.get_mut(pos / self.block) += 1;
     }
 
     pub fn dec(&mut self, pos: Coord) {
         self.current -= 1;
         self.rows[pos.y as usize] -= 1;
         self.columns[pos.x as usize] -= 1;
-        *self.blocks.get_mut(pos / self.blocks.size()) -= 1;
+        *self.blocks.get_mut(pos / self.block) -= 1;
     }
 
     pub fn get_current(&self) -> usize {
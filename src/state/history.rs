@@ -0,0 +1,146 @@
+use std::collections::VecDeque;
+
+///How many steps can be undone, older ones are forgotten.
+const LIMIT: usize = 100;
+
+///Something that can tell what it held before a change and put it back.
+pub trait Undo: Clone {
+    type Change;
+
+    ///What `before` held where it differs from this, None if nothing changed.
+    fn changes_from(&self, before: &Self) -> Option<Self::Change>;
+
+    ///Puts back what the change holds, returning what it replaced so that can be put back in turn.
+    fn revert(&mut self, change: Self::Change) -> Self::Change;
+}
+
+///What was there before each change, so changes can be undone and redone. Only the parts
+///that changed are kept, and changes that left everything as it was are not kept at all.
+pub struct History<T: Undo> {
+    undo: VecDeque<T::Change>,
+    redo: Vec<T::Change>,
+}
+
+impl<T: Undo> History<T> {
+    pub fn new() -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    ///Makes a change and remembers what it replaced. Anything that was undone can no longer be redone,
+    ///unless the change did nothing.
+    pub fn change<R>(&mut self, state: &mut T, change: impl FnOnce(&mut T) -> R) -> R {
+        let before = state.clone();
+        let result = change(state);
+
+        if let Some(change) = state.changes_from(&before) {
+            if self.undo.len() == LIMIT {
+                self.undo.pop_front();
+            }
+
+            self.undo.push_back(change);
+            self.redo.clear();
+        }
+
+        result
+    }
+
+    ///Puts back what was there before the last change. returns false if there was nothing to undo.
+    pub fn undo(&mut self, state: &mut T) -> bool {
+        match self.undo.pop_back() {
+            Some(change) => {
+                self.redo.push(state.revert(change));
+                true
+            }
+            None => { false }
+        }
+    }
+
+    ///Puts back the last change that was undone. returns false if there was nothing to redo.
+    pub fn redo(&mut self, state: &mut T) -> bool {
+        match self.redo.pop() {
+            Some(change) => {
+                self.undo.push_back(state.revert(change));
+                true
+            }
+            None => { false }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+impl<T: Undo> Default for History<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use crate::state::State;
+
+    #[test]
+    fn across_guess_levels() {
+        let mut state = State::new(Coord { x: 2, y: 2 });
+        let mut history = History::new();
+
+        history.change(&mut state, |x| x.inc_guess());
+        history.change(&mut state, |x| x.set(Coord { x: 0, y: 0 }, 1));
+        history.change(&mut state, |x| x.dec_guess());
+        assert_eq!(state.finished(), 0);
+
+        // undoing the level change brings back the node it cleared.
+        assert!(history.undo(&mut state));
+        assert_eq!(state.nodes().get(Coord { x: 0, y: 0 }).get_num(), Some(1));
+        assert_eq!(state.guess_level(), 1);
+
+        assert!(history.undo(&mut state));
+        assert!(history.undo(&mut state));
+        assert!(!history.undo(&mut state));
+        assert_eq!(state.guess_level(), 0);
+
+        assert!(history.redo(&mut state));
+        assert!(history.redo(&mut state));
+        assert_eq!(state.finished(), 1);
+
+        history.change(&mut state, |x| x.toggle_mark(Coord { x: 1, y: 1 }, 2));
+        assert!(!history.redo(&mut state));
+    }
+
+    #[test]
+    fn skips_changes_that_did_nothing() {
+        let mut state = State::new(Coord { x: 2, y: 2 });
+        let mut history = History::new();
+
+        history.change(&mut state, |x| x.set(Coord { x: 0, y: 0 }, 1));
+        assert!(history.undo(&mut state));
+
+        // clearing an empty node changes nothing, so the undone set can still be redone.
+        history.change(&mut state, |x| x.clear(Coord { x: 0, y: 0 }));
+        assert!(history.redo(&mut state));
+        assert!(history.undo(&mut state));
+        assert!(!history.undo(&mut state));
+        assert_eq!(state.finished(), 0);
+    }
+
+    #[test]
+    fn forgets_the_oldest() {
+        let mut state = State::new(Coord { x: 2, y: 2 });
+        let mut history = History::new();
+
+        for i in 0..LIMIT + 5 {
+            history.change(&mut state, |x| x.toggle_mark(Coord { x: 0, y: 0 }, i % 2));
+        }
+
+        let undone = std::iter::from_fn(|| history.undo(&mut state).then_some(())).count();
+        assert_eq!(undone, LIMIT);
+    }
+}
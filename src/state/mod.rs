@@ -1,9 +1,31 @@
-
 mod state;
-pub use state::State;
+pub use state::{State, Solutions};
+
+mod region;
+pub use region::{Region, Unit, BlockError};
+use region::Layout;
+
+mod cage;
+pub use cage::{Cage, sum_range};
+use cage::sum_options;
+
+mod chess;
+pub use chess::Chess;
+
+mod edge;
+pub use edge::{Edge, Mark, broken_mark};
+
+mod multi;
+pub use multi::{MultiState, Shape};
 
 mod finished;
 use finished::Finished;
 
 mod node;
-pub use node::Node;
\ No newline at end of file
+pub use node::Node;
+
+mod candidates;
+pub use candidates::{Candidates, Word};
+
+mod history;
+pub use history::{History, Undo};
\ No newline at end of file
@@ -0,0 +1,447 @@
+use crate::prelude::*;
+
+use super::{State, Node, Candidates, Undo};
+
+///How the grids of a multi-grid puzzle overlap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shape {
+    ///Two grids sharing one corner block.
+    Twin,
+    ///Four grids around a middle one that shares a corner block with each, the classic Samurai.
+    Samurai,
+}
+
+impl Shape {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "twin"    => { Some(Self::Twin) }
+            "samurai" => { Some(Self::Samurai) }
+            _ => { None }
+        }
+    }
+
+    ///Name of the shape, also used when saving it.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Twin    => { "twin" }
+            Self::Samurai => { "samurai" }
+        }
+    }
+
+    ///Where the top left node of each grid goes in the whole puzzle, for grids with blocks of `sub_size`.
+    pub fn offsets(&self, sub_size: Coord) -> Vec<Coord> {
+        let size = sub_size.x * sub_size.y;
+        // one block of overlap along each side. the corner grids of a Samurai sit twice that far
+        // apart so the middle grid shares one block with each.
+        let overlap = Coord { x: size - sub_size.x, y: size - sub_size.y };
+        let apart = Coord { x: overlap.x * 2, y: overlap.y * 2 };
+
+        match self {
+            Self::Twin => {
+                vec![Coord { x: 0, y: 0 }, overlap]
+            }
+            Self::Samurai => {
+                vec![
+                    Coord { x: 0, y: 0 },
+                    Coord { x: apart.x, y: 0 },
+                    overlap,
+                    Coord { x: 0, y: apart.y },
+                    apart,
+                ]
+            }
+        }
+    }
+}
+
+///Several grids joined by the nodes they share, as in Samurai puzzles. Positions are given in
+///nodes from the top left of the whole puzzle, and a number set in a shared node is set in every
+///grid holding it so the possibilities and conflicts of each stay up to date.
+#[derive(Clone)]
+pub struct MultiState {
+    grids:   Vec<State>,
+    offsets: Vec<Coord>,
+    shape:   Shape,
+    ///Size of the whole puzzle in nodes.
+    size:    Coord,
+}
+
+impl MultiState {
+    pub fn new(shape: Shape, sub_size: Coord) -> Self {
+        let offsets = shape.offsets(sub_size);
+        let cells = sub_size.x * sub_size.y;
+
+        let size = offsets.iter().fold(Coord { x: 0, y: 0 }, |size, offset| Coord {
+            x: size.x.max(offset.x + cells),
+            y: size.y.max(offset.y + cells),
+        });
+
+        Self {
+            grids: offsets.iter().map(|_| State::new(sub_size)).collect(),
+            offsets,
+            shape,
+            size,
+        }
+    }
+
+    pub fn shape(&self) -> Shape {
+        self.shape
+    }
+
+    pub fn sub_size(&self) -> Coord {
+        self.grids[0].sub_size()
+    }
+
+    ///Size of the whole puzzle in nodes, including the gaps between grids.
+    pub fn size(&self) -> Coord {
+        self.size
+    }
+
+    pub fn grids(&self) -> &[State] {
+        &self.grids
+    }
+
+    ///Where the top left node of each grid is in the whole puzzle.
+    pub fn offsets(&self) -> &[Coord] {
+        &self.offsets
+    }
+
+    ///Every grid holding the node, with the position of the node inside it.
+    pub fn places(&self, pos: Coord) -> impl Iterator<Item = (usize, Coord)> + '_ {
+        let cells = self.grids[0].size() as i32;
+
+        self.offsets.iter().enumerate().filter_map(move |(i, offset)| {
+            let inner = pos - *offset;
+            let inside = (0..cells).contains(&inner.x) && (0..cells).contains(&inner.y);
+
+            inside.then_some((i, inner))
+        })
+    }
+
+    ///True if some grid holds the node, false for the gaps between grids.
+    pub fn contains(&self, pos: Coord) -> bool {
+        self.places(pos).next().is_some()
+    }
+
+    ///The node as seen by the first grid holding it.
+    pub fn node(&self, pos: Coord) -> Option<&Node> {
+        self.places(pos).next().map(|(i, inner)| self.grids[i].nodes().get(inner))
+    }
+
+    ///Every node of the whole puzzle, with shared nodes only given once.
+    pub fn all_iter(&self) -> impl Iterator<Item = Coord> + '_ {
+        CoordIter::new(Coord { x: 0, y: 0 }, self.size).filter(|x| self.contains(*x))
+    }
+
+    ///Sets the node in every grid holding it.
+    pub fn set(&mut self, pos: Coord, num: usize) {
+        for (i, inner) in self.places(pos).collect::<Vec<_>>() {
+            self.grids[i].set(inner, num);
+        }
+    }
+
+    ///Clears the node in every grid holding it.
+    pub fn clear(&mut self, pos: Coord) {
+        for (i, inner) in self.places(pos).collect::<Vec<_>>() {
+            self.grids[i].clear(inner);
+        }
+    }
+
+    ///True if the node was found at guess level 0, which holds the givens.
+    pub fn is_given(&self, pos: Coord) -> bool {
+        matches!(self.node(pos), Some(Node::Found(data)) if data.guess_level == 0)
+    }
+
+    pub fn guess_level(&self) -> usize {
+        self.grids[0].guess_level()
+    }
+
+    ///Raises the guess level of every grid, so the numbers set from now on are kept apart from the givens.
+    pub fn inc_guess(&mut self) {
+        for grid in &mut self.grids {
+            grid.inc_guess();
+        }
+    }
+
+    ///Lowers the guess level of every grid, clearing the numbers set above it.
+    pub fn dec_guess(&mut self) {
+        for grid in &mut self.grids {
+            grid.dec_guess();
+        }
+    }
+
+    ///The next node of the puzzle from `pos` going by `amount`, carrying on over the gaps between
+    ///grids and wrapping around the edges of the whole puzzle.
+    pub fn step(&self, pos: Coord, amount: Coord) -> Coord {
+        let size = self.size;
+        let mut next = pos;
+
+        loop {
+            next = Coord {
+                x: (((next.x + amount.x) % size.x) + size.x) % size.x,
+                y: (((next.y + amount.y) % size.y) + size.y) % size.y,
+            };
+
+            if self.contains(next) || next == pos { return next }
+        }
+    }
+
+    pub fn finished(&self) -> usize {
+        self.all_iter().filter(|x| self.node(*x).is_some_and(|node| node.is_found())).count()
+    }
+
+    pub fn total(&self) -> usize {
+        self.all_iter().count()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.grids.iter().all(|x| x.is_done())
+    }
+
+    ///Returns true if any grid has a conflict, or a shared node was solved differently in two grids.
+    pub fn has_conflicts(&self) -> bool {
+        self.grids.iter().any(|x| x.has_conflicts()) || self.all_iter().any(|pos| {
+            let mut nums = self.places(pos).filter_map(|(i, inner)| self.grids[i].nodes().get(inner).get_num());
+            let first = nums.next();
+
+            nums.any(|num| Some(num) != first)
+        })
+    }
+
+    ///Which numbers the node can still be in every grid holding it, None once it is found.
+    pub fn possible(&self, pos: Coord) -> Option<Candidates> {
+        let mut possible = Candidates::full(self.grids[0].size());
+
+        for (i, inner) in self.places(pos) {
+            possible = possible.intersection(*self.grids[i].nodes().get(inner).possibilities()?);
+        }
+
+        Some(possible)
+    }
+
+    ///Passes what one grid knows about a shared node on to the others, a number found in one is set
+    ///in the rest and possibilities ruled out in one are eliminated from the rest. Returns true if anything changed.
+    fn share(&mut self) -> bool {
+        let mut changed = false;
+
+        for pos in CoordIter::new(Coord { x: 0, y: 0 }, self.size) {
+            let places: Vec<_> = self.places(pos).collect();
+            if places.len() < 2 { continue; }
+
+            let found = places.iter().find_map(|(i, inner)| self.grids[*i].nodes().get(*inner).get_num());
+
+            if let Some(num) = found {
+                for (i, inner) in &places {
+                    if !self.grids[*i].nodes().get(*inner).is_found() {
+                        self.grids[*i].set(*inner, num);
+                        changed = true;
+                    }
+                }
+
+                continue;
+            }
+
+            let possible = self.possible(pos).expect("none of the grids have found the node");
+
+            for (i, inner) in &places {
+                let ruled_out = self.grids[*i].nodes().get(*inner).possibilities()
+                    .map_or(Candidates::empty(), |x| x.difference(possible));
+
+                for num in ruled_out.iter() {
+                    changed |= self.grids[*i].eliminate(*inner, num);
+                }
+            }
+        }
+
+        changed
+    }
+
+    ///Solves each grid as far as it can without guessing, sharing what they find until none of
+    ///them can go further. Returns false if a conflict was found.
+    pub fn full_solve_no_guessing(&mut self) -> bool {
+        loop {
+            for grid in &mut self.grids {
+                if !grid.full_solve_no_guessing() { return false; }
+            }
+
+            if !self.share() { return !self.has_conflicts(); }
+        }
+    }
+
+    ///Solves the whole puzzle, guessing on the node with the fewest possibilities when the grids get stuck.
+    ///The puzzle is left as it was if there is no solution.
+    pub fn full_solve(&mut self) -> bool {
+        let mut next = self.clone();
+
+        if !next.full_solve_no_guessing() { return false; }
+        if next.is_done() {
+            *self = next;
+            return true
+        }
+
+        let guess = next.all_iter()
+            .filter_map(|pos| Some((pos, next.possible(pos)?)))
+            .min_by_key(|(_, possible)| possible.count());
+
+        let Some((pos, possible)) = guess else { return false };
+
+        for num in possible.iter() {
+            let mut attempt = next.clone();
+            attempt.set(pos, num);
+
+            if attempt.full_solve() {
+                *self = attempt;
+                return true
+            }
+        }
+
+        false
+    }
+}
+
+impl Undo for MultiState {
+    ///What each grid held before, None for the grids that didn't change.
+    type Change = Vec<Option<<State as Undo>::Change>>;
+
+    fn changes_from(&self, before: &Self) -> Option<Self::Change> {
+        let changes: Vec<_> = self.grids.iter().zip(&before.grids).map(|(grid, old)| grid.changes_from(old)).collect();
+
+        changes.iter().any(|x| x.is_some()).then_some(changes)
+    }
+
+    fn revert(&mut self, change: Self::Change) -> Self::Change {
+        self.grids.iter_mut()
+            .zip(change)
+            .map(|(grid, change)| change.map(|x| grid.revert(x)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samurai() -> MultiState {
+        MultiState::new(Shape::Samurai, Coord { x: 3, y: 3 })
+    }
+
+    #[test]
+    fn samurai_layout() {
+        let multi = samurai();
+
+        assert_eq!(multi.size(), Coord { x: 21, y: 21 });
+        assert_eq!(multi.offsets()[2], Coord { x: 6, y: 6 });
+        // the middle grid shares a block with each corner grid, the rest are held by one grid.
+        assert_eq!(multi.total(), 5 * 81 - 4 * 9);
+        assert_eq!(multi.places(Coord { x: 7, y: 7 }).count(), 2);
+        assert_eq!(multi.places(Coord { x: 10, y: 10 }).count(), 1);
+        assert!(!multi.contains(Coord { x: 10, y: 1 }));
+    }
+
+    #[test]
+    fn shared_nodes_for_every_block_size() {
+        let blocks = [Coord { x: 2, y: 2 }, Coord { x: 3, y: 2 }, Coord { x: 2, y: 3 }, Coord { x: 3, y: 3 }, Coord { x: 4, y: 3 }];
+
+        for sub_size in blocks {
+            let block = (sub_size.x * sub_size.y) as usize;
+            let grid = block * block;
+
+            for (shape, grids) in [(Shape::Twin, 2), (Shape::Samurai, 5)] {
+                let multi = MultiState::new(shape, sub_size);
+                let shared = multi.all_iter().filter(|x| multi.places(*x).count() > 1).count();
+
+                // every grid after the first shares exactly one block with another.
+                assert_eq!(shared, (grids - 1) * block, "{} {:?}", shape.name(), sub_size);
+                assert_eq!(multi.total(), grids * grid - shared);
+                assert!(multi.all_iter().all(|x| multi.places(x).count() <= 2));
+            }
+        }
+    }
+
+    #[test]
+    fn shared_nodes_update_every_grid() {
+        let mut multi = samurai();
+        let shared = Coord { x: 7, y: 7 };
+
+        multi.set(shared, 4);
+
+        // the top left grid sees it in its last block, the middle grid in its first.
+        assert!(!multi.grids()[0].nodes().get(Coord { x: 7, y: 0 }).possibilities().unwrap().has(4));
+        assert!(!multi.grids()[2].nodes().get(Coord { x: 1, y: 5 }).possibilities().unwrap().has(4));
+
+        multi.set(Coord { x: 7, y: 12 }, 4);
+        assert!(multi.has_conflicts());
+        assert!(multi.grids()[2].nodes().get(Coord { x: 1, y: 1 }).has_conflicts());
+
+        multi.clear(shared);
+        assert!(!multi.has_conflicts());
+        assert_eq!(multi.finished(), 1);
+    }
+
+    #[test]
+    fn step_skips_gaps() {
+        let multi = samurai();
+
+        // from the top left grid across the gap to the top right one.
+        assert_eq!(multi.step(Coord { x: 8, y: 0 }, Coord { x: 1, y: 0 }), Coord { x: 12, y: 0 });
+        assert_eq!(multi.step(Coord { x: 12, y: 0 }, Coord { x: -1, y: 0 }), Coord { x: 8, y: 0 });
+        // down through the middle grid, which fills the gap between the left grids only in its columns.
+        assert_eq!(multi.step(Coord { x: 1, y: 8 }, Coord { x: 0, y: 1 }), Coord { x: 1, y: 12 });
+        assert_eq!(multi.step(Coord { x: 7, y: 8 }, Coord { x: 0, y: 1 }), Coord { x: 7, y: 9 });
+        // wrapping around the right edge, and round to the start on a row with one node.
+        assert_eq!(multi.step(Coord { x: 20, y: 0 }, Coord { x: 1, y: 0 }), Coord { x: 0, y: 0 });
+        assert_eq!(multi.step(Coord { x: 10, y: 10 }, Coord { x: 0, y: 0 }), Coord { x: 10, y: 10 });
+    }
+
+    #[test]
+    fn undo_in_every_grid() {
+        let mut multi = samurai();
+        let mut history = crate::state::History::new();
+
+        history.change(&mut multi, |x| x.set(Coord { x: 7, y: 7 }, 4));
+        history.change(&mut multi, |x| x.clear(Coord { x: 0, y: 0 }));
+        assert!(history.undo(&mut multi));
+        assert!(!history.undo(&mut multi));
+
+        assert_eq!(multi.finished(), 0);
+        assert!(multi.grids()[2].nodes().get(Coord { x: 1, y: 1 }).possibilities().unwrap().has(4));
+
+        assert!(history.redo(&mut multi));
+        assert_eq!(multi.grids()[0].nodes().get(Coord { x: 7, y: 7 }).get_num(), Some(4));
+        assert_eq!(multi.grids()[2].nodes().get(Coord { x: 1, y: 1 }).get_num(), Some(4));
+    }
+
+    #[test]
+    fn solve_twin() {
+        let mut multi = MultiState::new(Shape::Twin, Coord { x: 3, y: 3 });
+
+        assert!(multi.full_solve());
+        assert!(multi.is_done());
+        assert!(!multi.has_conflicts());
+
+        // givens taken from the solution are kept by the next solve.
+        let solved = multi.clone();
+        let mut puzzle = MultiState::new(Shape::Twin, Coord { x: 3, y: 3 });
+        let givens: Vec<_> = solved.all_iter().step_by(3).collect();
+
+        for pos in &givens {
+            puzzle.set(*pos, solved.node(*pos).unwrap().get_num().unwrap());
+        }
+
+        assert!(puzzle.full_solve());
+        assert!(!puzzle.has_conflicts());
+
+        for pos in &givens {
+            assert_eq!(puzzle.node(*pos).unwrap().get_num(), solved.node(*pos).unwrap().get_num());
+        }
+    }
+
+    #[test]
+    fn solve_samurai() {
+        let mut multi = samurai();
+
+        assert!(multi.full_solve());
+        assert!(multi.is_done());
+        assert!(!multi.has_conflicts());
+        assert_eq!(multi.finished(), multi.total());
+    }
+}
@@ -1,6 +1,7 @@
 use crate::prelude::*;
+use super::Candidates;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct FoundData {
     pub guess_level: usize,
     pub guess_id:    Option<usize>,
@@ -8,15 +9,15 @@ pub struct FoundData {
     pub conflicts:   Vec<Coord>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Node {
     Found(FoundData),
-    Possible(Vec<bool>),
+    Possible(Candidates),
 }
 
 impl Node {
     pub fn new(size: usize) -> Self {
-        Self::Possible(vec![true; size])
+        Self::Possible(Candidates::full(size))
     }
 
     pub fn solution(&self) -> Option<usize> {
@@ -28,7 +29,7 @@ impl Node {
         }
     }
 
-    pub fn possibilities(&self) -> Option<& Vec<bool>> {
+    pub fn possibilities(&self) -> Option<&Candidates> {
         if let Self::Possible(list) = self {
             Some(&list)
         }
@@ -39,7 +40,7 @@ impl Node {
 
     pub fn num_possibilities(&self) -> Option<usize> {
         if let Self::Possible(list) = self {
-            Some(list.iter().filter(|x| **x).count())
+            Some(list.count())
         }
         else {
             None
@@ -101,7 +102,7 @@ impl Node {
 
     pub fn has_possibilities(&self) -> bool {
         if let Self::Possible(ref list) = self {
-            list.iter().filter(|x| **x).count() > 0
+            !list.is_empty()
         }
         else {
             true
@@ -114,14 +115,14 @@ impl Node {
                 !data.conflicts.is_empty()
             }
             Self::Possible(ref list) => {
-                list.iter().filter(|x| **x).count() == 0
+                list.is_empty()
             }
         }
     }
 
-    pub fn reset_possible(&mut self) {
+    pub fn reset_possible(&mut self, size: usize) {
         if let Self::Possible(ref mut list) = self {
-            list.fill(true);
+            *list = Candidates::full(size);
         }
     }
 
@@ -150,13 +151,13 @@ impl Node {
     }
 
     pub fn reset(&mut self, size: usize) {
-        *self = Self::Possible(vec![true; size]);
+        *self = Self::Possible(Candidates::full(size));
     }
 
     pub fn count(&self) -> Option<usize> {
         match self {
             Self::Possible(ref list) => {
-                Some(list.iter().filter(|x| **x).count())
+                Some(list.count())
             }
             Self::Found{..} => {
                 None
@@ -176,24 +177,12 @@ impl Node {
 
     pub fn try_solve(&mut self, guess: usize) -> Option<usize> {
         if let Self::Possible(ref list) = self {
-            let mut found = None;
-
-            for (i, x) in list.iter().enumerate() {
-                if *x {
-                    if found.is_none() {
-                        found = Some(i);
-                    }
-                    else {
-                        return None
-                    }
-                }
-            }
+            if list.count() != 1 { return None }
 
-            if let Some(num) = found {
-                self.set(num, guess);
+            let num = list.first().unwrap();
+            self.set(num, guess);
 
-                return Some(num)
-            }
+            return Some(num)
         }
         None
     }
@@ -0,0 +1,322 @@
+use std::fmt;
+
+use crate::prelude::*;
+
+use super::{Cage, Chess, Edge, Mark};
+use super::edge::{neighbours, broken_mark};
+
+///What a region is, counting from 0. Blocks are counted left to right then top to bottom.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Unit {
+    Row(i32),
+    Column(i32),
+    Block(i32),
+    ///0 runs from the top left corner to the bottom right, 1 from the top right to the bottom left.
+    Diagonal(i32),
+    ///The extra blocks of windoku puzzles, counted like blocks.
+    Window(i32),
+    ///The nodes of a killer cage, which can't repeat a number but don't need every number.
+    Cage(i32),
+}
+
+impl Unit {
+    ///Name for showing the user, counting from 1.
+    pub fn name(&self) -> String {
+        match self {
+            Self::Row(i)      => { format!("row {}", i + 1) }
+            Self::Column(i)   => { format!("column {}", i + 1) }
+            Self::Block(i)    => { format!("block {}", i + 1) }
+            Self::Diagonal(0) => { "main diagonal".to_string() }
+            Self::Diagonal(_) => { "anti-diagonal".to_string() }
+            Self::Window(i)   => { format!("window {}", i + 1) }
+            Self::Cage(i)     => { format!("cage {}", i + 1) }
+        }
+    }
+}
+
+///A group of nodes that all need different numbers. Rows, columns and blocks are regions,
+///and variants can add their own without the solvers needing to know about them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Region {
+    pub unit:  Unit,
+    pub cells: Vec<Coord>,
+}
+
+impl Region {
+    pub fn new<T: IntoIterator<Item = Coord>>(unit: Unit, cells: T) -> Self {
+        Self {
+            unit,
+            cells: cells.into_iter().collect(),
+        }
+    }
+
+    pub fn contains(&self, pos: Coord) -> bool {
+        self.cells.contains(&pos)
+    }
+
+    ///True if the region has a node for every number, so each number has to go somewhere in it.
+    ///Smaller regions like cages only stop numbers from repeating.
+    pub fn is_full(&self, size: usize) -> bool {
+        self.cells.len() == size
+    }
+
+    ///The rows, columns and blocks of a grid with blocks of the given width and height.
+    pub fn standard(sub_size: Coord) -> Vec<Self> {
+        let size = sub_size.x * sub_size.y;
+        let blocks = Coord { x: sub_size.y, y: sub_size.x };
+
+        let rows = (0..size).map(|y| {
+            Self::new(Unit::Row(y), CoordIter::new(Coord { x: 0, y }, Coord { x: size, y: y + 1 }))
+        });
+
+        let columns = (0..size).map(|x| {
+            Self::new(Unit::Column(x), CoordIter::new(Coord { x, y: 0 }, Coord { x: x + 1, y: size }))
+        });
+
+        let blocks = CoordIter::new(Coord { x: 0, y: 0 }, blocks)
+            .enumerate()
+            .map(|(i, block)| {
+                let start = Coord { x: block.x * sub_size.x, y: block.y * sub_size.y };
+                let end = Coord { x: start.x + sub_size.x, y: start.y + sub_size.y };
+                Self::new(Unit::Block(i as i32), CoordIter::new(start, end))
+            });
+
+        rows.chain(columns).chain(blocks).collect()
+    }
+
+    ///Which block each node of a grid with blocks of the given width and height is in.
+    pub fn block_map(sub_size: Coord) -> Vec2D<usize> {
+        let size = sub_size.x * sub_size.y;
+        let mut map = Vec2D::new(Coord::same(size), 0);
+
+        for pos in CoordIter::new(Coord { x: 0, y: 0 }, Coord::same(size)) {
+            *map.get_mut(pos) = ((pos.y / sub_size.y) * sub_size.y + pos.x / sub_size.x) as usize;
+        }
+
+        map
+    }
+
+    ///Makes blocks from a map of which block each node is in, so they can be any shape. Every block
+    ///needs as many nodes as there are numbers, all joined up through their sides.
+    pub fn blocks(map: &Vec2D<usize>) -> Result<Vec<Self>, BlockError> {
+        let size = map.size().x as usize;
+        let mut blocks = vec![Vec::new(); size];
+
+        for pos in CoordIter::new(Coord { x: 0, y: 0 }, map.size()) {
+            let block = *map.get(pos);
+            if block >= size { return Err(BlockError::OutOfRange(block)) }
+
+            blocks[block].push(pos);
+        }
+
+        for (block, cells) in blocks.iter().enumerate() {
+            if cells.len() != size { return Err(BlockError::Size(block, cells.len())) }
+
+            // spread out from the first node through neighbours in the same block.
+            let mut reached = vec![cells[0]];
+            let mut i = 0;
+
+            while i < reached.len() {
+                let pos = reached[i];
+                i += 1;
+
+                for step in [Coord { x: 1, y: 0 }, Coord { x: -1, y: 0 }, Coord { x: 0, y: 1 }, Coord { x: 0, y: -1 }] {
+                    let next = Coord { x: pos.x + step.x, y: pos.y + step.y };
+
+                    if cells.contains(&next) && !reached.contains(&next) {
+                        reached.push(next);
+                    }
+                }
+            }
+
+            if reached.len() != size { return Err(BlockError::Split(block)) }
+        }
+
+        Ok(blocks.into_iter()
+            .enumerate()
+            .map(|(i, cells)| Self::new(Unit::Block(i as i32), cells))
+            .collect())
+    }
+
+    ///The windows of a windoku puzzle with square blocks of the given width. Windows are the size of
+    ///a block and sit one node in from the edge with a gap of one node between them, so a 9x9 grid
+    ///has four and a 16x16 grid has nine.
+    pub fn windows(width: i32) -> Vec<Self> {
+        let per_side = width - 1;
+
+        CoordIter::new(Coord { x: 0, y: 0 }, Coord::same(per_side))
+            .enumerate()
+            .map(|(i, window)| {
+                let start = Coord { x: 1 + window.x * (width + 1), y: 1 + window.y * (width + 1) };
+                let end = Coord { x: start.x + width, y: start.y + width };
+                Self::new(Unit::Window(i as i32), CoordIter::new(start, end))
+            })
+            .collect()
+    }
+
+    ///The two corner to corner diagonals of a grid with the given number of nodes along each side.
+    pub fn diagonals(size: i32) -> Vec<Self> {
+        vec![
+            Self::new(Unit::Diagonal(0), (0..size).map(|i| Coord { x: i, y: i })),
+            Self::new(Unit::Diagonal(1), (0..size).map(|i| Coord { x: size - 1 - i, y: i })),
+        ]
+    }
+}
+
+///Why a map of blocks can't be used. Blocks are counted from 0.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlockError {
+    ///A block number too large for the size of the puzzle.
+    OutOfRange(usize),
+    ///A block with the wrong number of nodes, along with how many it has.
+    Size(usize, usize),
+    ///A block that is in more than one piece.
+    Split(usize),
+}
+
+impl fmt::Display for BlockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::OutOfRange(block) => {
+                write!(f, "block {} is more than the number of blocks", block + 1)
+            }
+            Self::Size(block, count) => {
+                write!(f, "block {} has {} nodes", block + 1, count)
+            }
+            Self::Split(block) => {
+                write!(f, "block {} is not in one piece", block + 1)
+            }
+        }
+    }
+}
+
+///Every region, cage, chess constraint and edge mark of a puzzle, along with the regions and peers of each node
+///so they don't have to be searched for. Copies of a state share one layout until a region is added.
+#[derive(Clone)]
+pub struct Layout {
+    regions:      Vec<Region>,
+    cages:        Vec<Cage>,
+    chess:        Vec<Chess>,
+    edges:        Vec<Edge>,
+    ///Marks that don't hold between neighbours without one.
+    negative:     Vec<Mark>,
+    node_regions: Vec2D<Vec<usize>>,
+    peers:        Vec2D<Vec<Coord>>,
+}
+
+impl Layout {
+    pub fn new(size: usize, regions: Vec<Region>) -> Self {
+        let mut layout = Self {
+            regions:      Vec::new(),
+            cages:        Vec::new(),
+            chess:        Vec::new(),
+            edges:        Vec::new(),
+            negative:     Vec::new(),
+            node_regions: Vec2D::new(Coord::same(size as i32), Vec::new()),
+            peers:        Vec2D::new(Coord::same(size as i32), Vec::new()),
+        };
+
+        for region in regions {
+            layout.add(region);
+        }
+
+        layout
+    }
+
+    pub fn add(&mut self, region: Region) {
+        let index = self.regions.len();
+
+        for pos in &region.cells {
+            self.node_regions.get_mut(*pos).push(index);
+
+            for other in &region.cells {
+                let peers = self.peers.get_mut(*pos);
+
+                if other != pos && !peers.contains(other) {
+                    peers.push(*other);
+                }
+            }
+        }
+
+        self.regions.push(region);
+    }
+
+    ///Adds a cage along with a region for it, so its nodes can't repeat a number.
+    pub fn add_cage(&mut self, cage: Cage) {
+        self.add(Region::new(Unit::Cage(self.cages.len() as i32), cage.cells.iter().copied()));
+        self.cages.push(cage);
+    }
+
+    ///Makes every node a peer of the nodes one chess move away, without them sharing a region.
+    pub fn add_chess(&mut self, chess: Chess) {
+        let size = self.peers.size().x;
+
+        for pos in CoordIter::new(Coord { x: 0, y: 0 }, self.peers.size()) {
+            let peers = self.peers.get_mut(pos);
+
+            for other in chess.moves(pos, size) {
+                if !peers.contains(&other) {
+                    peers.push(other);
+                }
+            }
+        }
+
+        self.chess.push(chess);
+    }
+
+    pub fn add_edge(&mut self, edge: Edge) {
+        self.edges.push(edge);
+    }
+
+    pub fn set_negative(&mut self, marks: &[Mark]) {
+        self.negative = marks.iter().copied().filter(|x| x.can_be_negative()).collect();
+    }
+
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    pub fn cages(&self) -> &[Cage] {
+        &self.cages
+    }
+
+    pub fn chess(&self) -> &[Chess] {
+        &self.chess
+    }
+
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    pub fn negative(&self) -> &[Mark] {
+        &self.negative
+    }
+
+    ///The nodes whose numbers are limited by the node's through edge marks. With negative
+    ///constraints that is every neighbour, otherwise only the ones across a mark.
+    pub fn linked(&self, pos: Coord) -> Vec<Coord> {
+        if !self.negative.is_empty() {
+            return neighbours(pos, self.peers.size().x)
+        }
+
+        self.edges.iter()
+            .filter_map(|x| if x.a == pos { Some(x.b) } else if x.b == pos { Some(x.a) } else { None })
+            .collect()
+    }
+
+    ///The mark the numbers of two neighbours break, and whether it is broken by being left out.
+    ///Negative marks are broken by numbers that would need the mark when there isn't one.
+    pub fn broken_mark(&self, a: Coord, num: usize, b: Coord, other: usize) -> Option<(Mark, bool)> {
+        broken_mark(&self.edges, &self.negative, a, num, b, other, self.peers.size().x)
+    }
+
+    ///Indices of the regions the node is in.
+    pub fn node_regions(&self, pos: Coord) -> &[usize] {
+        self.node_regions.get(pos)
+    }
+
+    ///Every other node that shares a region with the node, each only once.
+    pub fn peers(&self, pos: Coord) -> &[Coord] {
+        self.peers.get(pos)
+    }
+}
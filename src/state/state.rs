@@ -1,6 +1,9 @@
 use crate::prelude::*;
 use super::Finished;
 use super::Node;
+use super::Candidates;
+use super::{Region, Layout, Unit, BlockError, Cage, Chess, Edge, Mark, Undo, sum_range, sum_options};
+use std::rc::Rc;
 
 
 #[derive(Clone)]
@@ -10,11 +13,46 @@ enum Search {
     ToMany,
 }
 
+///How many ways a puzzle can be finished, with examples of the finished grids.
+pub enum Solutions {
+    None,
+    Unique(Vec2D<usize>),
+    Multiple(Vec2D<usize>, Vec2D<usize>),
+}
+
+impl Solutions {
+    ///Takes the grids found by a search that stopped after two solutions.
+    pub fn new(mut found: Vec<Vec2D<usize>>) -> Self {
+        match (found.pop(), found.pop()) {
+            (Some(second), Some(first)) => { Self::Multiple(first, second) }
+            (Some(first), None) => { Self::Unique(first) }
+            _ => { Self::None }
+        }
+    }
+}
+
+///What a state held before a change, only the nodes that changed are kept.
+pub struct Change {
+    nodes:       Vec<(Coord, Node)>,
+    eliminated:  Vec<(Coord, Vec<Option<usize>>)>,
+    marks:       Vec<(Coord, Candidates)>,
+    finished:    Finished,
+    guess_level: usize,
+    guesses:     Vec<Coord>,
+    guess_count: usize,
+    layout:      Rc<Layout>,
+}
+
+#[derive(Clone)]
 pub struct State {
     nodes:       Vec2D<Node>,
     finished:    Finished,
     guess_level: usize,
     guesses:     Vec<Coord>,
+    guess_count: usize,
+    eliminated:  Vec2D<Vec<Option<usize>>>,
+    marks:       Vec2D<Candidates>,
+    layout:      Rc<Layout>,
     size:        usize,
     blocks:      Coord,
 }
@@ -32,15 +70,56 @@ impl State {
             finished:    Finished::new(size, blocks),
             guess_level: 0,
             guesses:     Vec::new(),
+            guess_count: 0,
+            eliminated:  Vec2D::new(Coord {x: size as i32, y: size as i32}, vec![None; size]),
+            marks:       Vec2D::new(Coord {x: size as i32, y: size as i32}, Candidates::empty()),
+            layout:      Rc::new(Layout::new(size, Region::standard(sub_size))),
             size,
             blocks,
         }
     }
 
+    ///Rebuilds a saved state. Each found node and elimination is given as (position, number, guess level),
+    ///and the guesses are the positions of the guessed nodes in the order they were made.
+    pub fn restore(
+        sub_size: Coord,
+        guess_level: usize,
+        found: &[(Coord, usize, usize)],
+        eliminations: &[(Coord, usize, usize)],
+        guesses: Vec<Coord>
+    ) -> Self {
+        let mut state = Self::new(sub_size);
+
+        for (pos, num, level) in eliminations {
+            state.eliminated.get_mut(*pos)[*num] = Some(*level);
+        }
+
+        let mut found = found.to_vec();
+        found.sort_by_key(|x| x.2);
+
+        for (pos, num, level) in found {
+            state.guess_level = level;
+            state.set(pos, num);
+        }
+
+        for (id, pos) in guesses.iter().enumerate() {
+            state.nodes.get_mut(*pos).set_guess_id(id);
+        }
+
+        state.guesses = guesses;
+        state.guess_level = guess_level;
+        state.reset_possible();
+        state
+    }
+
     pub fn size(&self) -> usize {
         self.size
     }
 
+    pub fn sub_size(&self) -> Coord {
+        Coord { x: self.blocks.y, y: self.blocks.x }
+    }
+
     pub fn nodes(&self) -> &Vec2D<Node> {
         &self.nodes
     }
@@ -49,6 +128,20 @@ impl State {
         self.guess_level
     }
 
+    ///Number of guesses the auto solver has tried, including ones that were later undone.
+    pub fn guess_count(&self) -> usize {
+        self.guess_count
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.finished.done()
+    }
+
+    ///Positions of the guesses made by the auto solver, oldest first.
+    pub fn guesses(&self) -> &Vec<Coord> {
+        &self.guesses
+    }
+
     pub fn finished(&self) -> usize {
         self.finished.get_current()
     }
@@ -59,7 +152,9 @@ impl State {
 
     ///Set a node to be solved as the given number, with the current guess level.
     pub fn set(&mut self, pos: Coord, solve: usize) {
-        if self.nodes.get(pos).is_found() {
+        let replaced = self.nodes.get(pos).get_num();
+
+        if replaced.is_some() {
             self.clear_conflicts(pos);
         }
         else {
@@ -71,23 +166,32 @@ impl State {
         node.set(solve, self.guess_level);
 
         self.set_conflicts(pos);
-        self.reset_possible();
+
+        // a replaced number may now be possible again around the node.
+        if replaced.is_some_and(|x| x != solve) {
+            self.recompute_peers(pos);
+        }
+        else {
+            self.point_possibilities(pos);
+        }
     }
 
     ///Removes the current solution from the node and recalculates the possibilities of the other nodes.
     pub fn clear(&mut self, pos: Coord) {
-        self.clear_conflicts(pos);
-        
-        let node = self.nodes.get_mut(pos);
+        if !self.nodes.get(pos).is_found() { return }
+
+        let guessed = self.nodes.get_mut(pos).get_guess_id().is_some();
 
-        *node = Node::new(self.size);
+        self.clear_conflicts(pos);
+        *self.nodes.get_mut(pos) = Node::new(self.size);
 
-        if let Some(guess_id) = node.get_guess_id() {
-            self.guesses.remove(guess_id);
+        // removing by id would leave the ids of later guesses pointing one past their place.
+        if guessed {
+            self.guesses.retain(|x| *x != pos);
         }
 
         self.finished.dec(pos);
-        self.reset_possible();
+        self.recompute_peers(pos);
     }
 
     pub fn inc_guess(&mut self) {
@@ -98,17 +202,26 @@ impl State {
         if self.guess_level == 0 { return }
         self.guess_level -= 1;
 
+        let mut restored = Vec::new();
+
+        for pos in self.all_iter() {
+            for level in self.eliminated.get_mut(pos) {
+                if level.is_some_and(|x| x > self.guess_level) {
+                    *level = None;
+                    restored.push(pos);
+                }
+            }
+        }
+
         for pos in self.all_iter() {
             if self.nodes.get_mut(pos).guess_reset(self.guess_level) {
                 self.clear(pos);
             }
         }
 
-        self.reset_possible();
-    }
-
-    fn pos_block(&self, pos: Coord) -> Coord {
-        Coord { x: pos.x/self.blocks.x, y: pos.y/self.blocks.y }
+        for pos in restored {
+            self.recompute(pos);
+        }
     }
 
     fn all_iter(&self) -> CoordIter {
@@ -118,51 +231,290 @@ impl State {
         )
     }
 
-    fn row_iter(&self, y: i32) -> CoordIter {
-        row_iter(self.size as i32, y)
+    ///Every group of nodes that need different numbers, the rows, columns and blocks first.
+    pub fn regions(&self) -> &[Region] {
+        self.layout.regions()
+    }
+
+    ///The regions a node is in.
+    pub fn node_regions(&self, pos: Coord) -> impl Iterator<Item = &Region> {
+        self.layout.node_regions(pos).iter().map(|i| &self.layout.regions()[*i])
     }
 
-    fn column_iter(&self, x: i32) -> CoordIter {
-        column_iter(self.size as i32, x)
+    ///Every other node that shares a region with the node.
+    pub fn peers(&self, pos: Coord) -> &[Coord] {
+        self.layout.peers(pos)
+    }
+
+    ///Adds both diagonals as regions, so they need every number once like a row does.
+    pub fn add_diagonals(&mut self) {
+        for region in Region::diagonals(self.size as i32) {
+            self.add_region(region);
+        }
+    }
+
+    ///True if the diagonals have been added as regions.
+    pub fn is_diagonal(&self) -> bool {
+        self.regions().iter().any(|x| matches!(x.unit, Unit::Diagonal(_)))
+    }
+
+    ///Adds the windows of a windoku puzzle as regions. Only for square blocks.
+    pub fn add_windows(&mut self) {
+        assert_eq!(self.sub_size().x, self.sub_size().y, "windows need square blocks");
+
+        for region in Region::windows(self.sub_size().x) {
+            self.add_region(region);
+        }
+    }
+
+    ///True if the windows have been added as regions.
+    pub fn is_windoku(&self) -> bool {
+        self.regions().iter().any(|x| matches!(x.unit, Unit::Window(_)))
+    }
+
+    ///Adds another group of nodes that need different numbers, then rechecks conflicts and possibilities.
+    pub fn add_region(&mut self, region: Region) {
+        Rc::make_mut(&mut self.layout).add(region);
+
+        self.reset_conflicts();
+        self.reset_possible();
+    }
+
+    ///The killer cages, in the order they were added.
+    pub fn cages(&self) -> &[Cage] {
+        self.layout.cages()
+    }
+
+    ///Adds a killer cage, its nodes can't repeat a number and have to add up to its sum.
+    ///Panics if the cage shares a node with another cage.
+    pub fn add_cage(&mut self, cage: Cage) {
+        assert!(!cage.overlaps(self.cages()), "cages can't share nodes");
+
+        Rc::make_mut(&mut self.layout).add_cage(cage);
+
+        self.reset_conflicts();
+        self.reset_possible();
+    }
+
+    ///The nodes whose numbers are limited by the node's through edge marks.
+    pub fn linked(&self, pos: Coord) -> Vec<Coord> {
+        self.layout.linked(pos)
+    }
+
+    ///The chess moves that can't join two nodes with the same number.
+    pub fn chess(&self) -> &[Chess] {
+        self.layout.chess()
+    }
+
+    ///Stops nodes one chess move apart from having the same number, as in anti-knight and anti-king puzzles.
+    pub fn add_chess(&mut self, chess: Chess) {
+        if self.chess().contains(&chess) { return }
+
+        Rc::make_mut(&mut self.layout).add_chess(chess);
+
+        self.reset_conflicts();
+        self.reset_possible();
+    }
+
+    ///The marks on edges between neighbouring nodes, in the order they were added.
+    pub fn edges(&self) -> &[Edge] {
+        self.layout.edges()
+    }
+
+    ///Adds a Kropki dot, X, V or greater-than sign between two neighbouring nodes.
+    pub fn add_edge(&mut self, edge: Edge) {
+        if self.edges().contains(&edge) { return }
+
+        Rc::make_mut(&mut self.layout).add_edge(edge);
+
+        self.reset_conflicts();
+    }
+
+    ///The marks that don't hold between neighbours without one.
+    pub fn negative(&self) -> &[Mark] {
+        self.layout.negative()
+    }
+
+    ///Sets which marks are given wherever they hold, so that neighbours without one can't fit it.
+    ///Greater-than signs are left out, as every pair of neighbours would need one.
+    pub fn set_negative(&mut self, marks: &[Mark]) {
+        Rc::make_mut(&mut self.layout).set_negative(marks);
+
+        self.reset_conflicts();
+    }
+
+    ///Why two nodes conflict, the edge mark their numbers break, or else the first region they share
+    ///or the chess move between them.
+    pub fn relation(&self, a: Coord, b: Coord) -> Option<String> {
+        let nums = (self.nodes.get(a).get_num(), self.nodes.get(b).get_num());
+
+        let edge = match nums {
+            (Some(num), Some(other)) => { self.layout.broken_mark(a, num, b, other) }
+            _ => { None }
+        };
+        let edge = edge.map(|(mark, negative)| {
+            if negative { format!("no {}", mark.mark_name()) } else { mark.mark_name().to_string() }
+        });
+
+        // nodes with the same number are explained by what they share first.
+        if nums.0 != nums.1 && edge.is_some() { return edge }
+
+        if let Some(region) = self.node_regions(a).find(|x| x.contains(b)) {
+            return Some(region.unit.name())
+        }
+
+        self.chess().iter().find(|x| x.joins(a, b)).map(|x| x.move_name().to_string()).or(edge)
+    }
+
+    ///Which block each node is in, counting from 0.
+    pub fn blocks(&self) -> Vec2D<usize> {
+        let mut map = Vec2D::new(self.nodes.size(), 0);
+
+        for region in self.regions() {
+            if let Unit::Block(i) = region.unit {
+                for pos in &region.cells {
+                    *map.get_mut(*pos) = i as usize;
+                }
+            }
+        }
+
+        map
     }
 
-    fn block_iter(&self, pos: Coord) -> CoordIter {
-        block_iter(self.blocks, pos)
+    ///Replaces the blocks with ones of any shape, as in jigsaw puzzles. Solved nodes are kept
+    ///and conflicts and possibilities are rechecked.
+    pub fn set_blocks(&mut self, blocks: &Vec2D<usize>) -> Result<(), BlockError> {
+        let mut regions = self.regions().to_vec();
+        regions.retain(|x| !matches!(x.unit, Unit::Block(_) | Unit::Cage(_)));
+
+        // blocks go straight after the rows and columns, like they do normally.
+        let split = 2 * self.size;
+        regions.splice(split..split, Region::blocks(blocks)?);
+
+        let mut layout = Layout::new(self.size, regions);
+        for cage in self.cages() {
+            layout.add_cage(cage.clone());
+        }
+
+        for chess in self.chess() {
+            layout.add_chess(*chess);
+        }
+
+        for edge in self.edges() {
+            layout.add_edge(*edge);
+        }
+
+        layout.set_negative(self.negative());
+
+        self.layout = Rc::new(layout);
+
+        self.reset_conflicts();
+        self.reset_possible();
+        Ok(())
     }
 
-    fn iter_iter(&self) -> impl Iterator<Item = CoordIter> {
-        iter_iter(self.size as i32, self.blocks)
+    ///True if the blocks aren't the usual rectangles.
+    pub fn is_jigsaw(&self) -> bool {
+        self.blocks().vec() != Region::block_map(self.sub_size()).vec()
     }
 
-    fn point_iter(&self, pos: Coord) -> impl Iterator<Item = CoordIter> {
-        [
-            self.row_iter(pos.y),
-            self.column_iter(pos.x),
-            self.block_iter(self.pos_block(pos))
-        ].into_iter()
+    fn reset_conflicts(&mut self) {
+        for pos in self.all_iter() {
+            let num = self.nodes.get(pos).get_num();
+            if num.is_none() { continue; }
+
+            let mut conflicts: Vec<Coord> = self.layout.peers(pos).iter()
+                .filter(|x| self.nodes.get(**x).get_num() == num)
+                .copied()
+                .collect();
+
+            conflicts.extend(self.edge_conflicts(pos));
+
+            if let Node::Found(data) = self.nodes.get_mut(pos) {
+                data.conflicts = conflicts;
+            }
+        }
     }
 
     fn reset_possible(&mut self) {
         for pos in self.all_iter() {
-            self.nodes.get_mut(pos).reset_possible();
+            self.recompute(pos);
+        }
+    }
+
+    ///Removes a possibility from a node so that it stays removed when possibilities are rechecked,
+    ///until the guess level drops below the current one. Returns true if the node still had the possibility.
+    pub fn eliminate(&mut self, pos: Coord, num: usize) -> bool {
+        if let Node::Possible(ref mut list) = self.nodes.get_mut(pos) {
+            if list.remove(num) {
+                self.eliminated.get_mut(pos)[num] = Some(self.guess_level);
+                return true
+            }
+        }
+
+        false
+    }
+
+    ///The pencil marks the user has put on a node, kept apart from the possibilities found by the solvers.
+    pub fn marks(&self, pos: Coord) -> &Candidates {
+        self.marks.get(pos)
+    }
+
+    pub fn toggle_mark(&mut self, pos: Coord, num: usize) {
+        self.marks.get_mut(pos).toggle(num);
+    }
+
+    ///Puts a pencil mark on a node, leaving it there if it already was.
+    pub fn add_mark(&mut self, pos: Coord, num: usize) {
+        self.marks.get_mut(pos).insert(num);
+    }
+
+    ///Every kept elimination as (position, number, guess level).
+    pub fn eliminations(&self) -> Vec<(Coord, usize, usize)> {
+        let mut all = Vec::new();
+
+        for pos in self.all_iter() {
+            for (num, level) in self.eliminated.get(pos).iter().enumerate() {
+                if let Some(level) = level {
+                    all.push((pos, num, *level));
+                }
+            }
         }
 
-        self.find_possible(self.iter_iter());
+        all
     }
 
     fn set_conflicts(&mut self, pos: Coord) {
         let num = self.nodes.get(pos).get_num();
 
-        for iter in self.point_iter(pos) {
-            for check in iter {
-                if pos == check { continue; }
+        let layout = Rc::clone(&self.layout);
 
-                if num == self.nodes.get(check).get_num() {
-                    self.nodes.get_mut(pos).add_conflict(check);
-                    self.nodes.get_mut(check).add_conflict(pos);
-                }
+        for check in layout.peers(pos).iter().copied() {
+            if num == self.nodes.get(check).get_num() {
+                self.nodes.get_mut(pos).add_conflict(check);
+                self.nodes.get_mut(check).add_conflict(pos);
             }
         }
+
+        for check in self.edge_conflicts(pos) {
+            self.nodes.get_mut(pos).add_conflict(check);
+            self.nodes.get_mut(check).add_conflict(pos);
+        }
+    }
+
+    ///The neighbours whose numbers break an edge mark with the node's. Peers with the same number
+    ///are left out, they already conflict.
+    fn edge_conflicts(&self, pos: Coord) -> Vec<Coord> {
+        let Some(num) = self.nodes.get(pos).get_num() else { return Vec::new() };
+
+        self.layout.linked(pos).into_iter()
+            .filter(|check| {
+                let Some(other) = self.nodes.get(*check).get_num() else { return false };
+                let counted = other == num && self.layout.peers(pos).contains(check);
+
+                !counted && self.layout.broken_mark(pos, num, *check, other).is_some()
+            })
+            .collect()
     }
 
     fn clear_conflicts(&mut self, pos: Coord) {
@@ -175,40 +527,47 @@ impl State {
         }
     }
 
-    ///Check what solutions are possible for the nodes given in the range of the iterator.
-    fn find_possible<T: Iterator<Item = CoordIter>>(&mut self, iter_iter: T) {
-        let mut list = vec![true; self.size];
-
-        for iter in iter_iter {
-            list.fill(true);
+    ///Check possible solutions for nodes effected by one point.
+    fn point_possibilities(&mut self, pos: Coord) {
+        let Some(num) = self.nodes.get(pos).get_num() else { return };
 
-            // get list of all solved in section.
-            for pos in iter.clone() {
-                let node = self.nodes.get(pos);
-                
-                if let Node::Found(data) = node {
-                    list[data.num] = false;
-                }
+        for check in self.layout.peers(pos) {
+            if let Node::Possible(ref mut list) = self.nodes.get_mut(*check) {
+                list.remove(num);
             }
+        }
+    }
 
-            // set possible of all 
-            for pos in iter {
-                let node = self.nodes.get_mut (pos);
+    ///Rebuilds the possibilities of an unsolved node from its eliminations and the found nodes it shares a group with.
+    fn recompute(&mut self, pos: Coord) {
+        if self.nodes.get(pos).is_found() { return }
 
-                if let Node::Possible(ref mut cur_list) = node {
-                    for i in 0..list.len() {
-                        if !list[i] {
-                            cur_list[i] = false;
-                        }
-                    }
-                }
+        let mut list = Candidates::full(self.size);
+
+        for (num, level) in self.eliminated.get(pos).iter().enumerate() {
+            if level.is_some() {
+                list.remove(num);
+            }
+        }
+
+        for check in self.layout.peers(pos) {
+            if let Some(num) = self.nodes.get(*check).get_num() {
+                list.remove(num);
             }
         }
+
+        *self.nodes.get_mut(pos) = Node::Possible(list);
     }
 
-    ///Check possible solutions for nodes effected by one point.
-    fn point_possibilities(&mut self, pos: Coord) {
-        self.find_possible(self.point_iter(pos))
+    ///Rebuilds the possibilities of a node and every node that shares a group with it.
+    fn recompute_peers(&mut self, pos: Coord) {
+        let layout = Rc::clone(&self.layout);
+
+        self.recompute(pos);
+
+        for check in layout.peers(pos) {
+            self.recompute(*check);
+        }
     }
 
     ///Try to solve unsolved nodes using there list of possibilities. returns true if any nodes were solved.
@@ -219,13 +578,10 @@ impl State {
             if let Some(_) = self.nodes.get_mut(pos).try_solve(self.guess_level) {
                 self.finished.inc(pos);
                 self.set_conflicts(pos);
+                self.point_possibilities(pos);
                 change = true;
             }
         }
-
-        if change {
-            self.find_possible(self.iter_iter());
-        }
         
         change
     }
@@ -239,25 +595,26 @@ impl State {
         let mut search = vec![Search::None; self.size];
         let mut change = false;
 
-        for iter in self.iter_iter() {
+        let layout = Rc::clone(&self.layout);
+        let numbers = self.size;
+
+        for region in layout.regions().iter().filter(|x| x.is_full(numbers)) {
             search.fill(Search::None);
 
             // find uniques
-            for pos in iter {
-                let node = self.nodes.get_mut(pos);
-
-                if let Node::Possible(ref mut list) = node {
-                    for i in 0..list.len() {
-                        if list[i] {
-                            match search[i] {
-                                Search::None => {
-                                    search[i] = Search::Found(pos, i);
-                                }
-                                Search::Found(_, _) => {
-                                    search[i] = Search::ToMany;
-                                }
-                                Search::ToMany => {}
+            for pos in &region.cells {
+                let node = self.nodes.get(*pos);
+
+                if let Node::Possible(list) = node {
+                    for i in list.iter() {
+                        match search[i] {
+                            Search::None => {
+                                search[i] = Search::Found(*pos, i);
                             }
+                            Search::Found(_, _) => {
+                                search[i] = Search::ToMany;
+                            }
+                            Search::ToMany => {}
                         }
                     }
                 }
@@ -278,22 +635,302 @@ impl State {
         while self.solve_exclusive() {}
     }
 
-    fn has_conflicts(&self) -> bool {
+    ///Locked candidates: when a number can only go where a region overlaps another region, it can't go
+    ///anywhere else in the other region. With the usual regions this is a block pointing along a line,
+    ///or a line claiming part of a block. returns true if any possibilities were removed.
+    pub fn solve_intersections(&mut self) -> bool {
+        let layout = Rc::clone(&self.layout);
+        let numbers = self.size;
+        let mut change = false;
+
+        // only regions that need every number are sure to hold it.
+        for (i, region) in layout.regions().iter().enumerate().filter(|(_, x)| x.is_full(numbers)) {
+            for num in 0..self.size {
+                let spots: Vec<Coord> = region.cells.iter().copied().filter(|pos| self.can_be(*pos, num)).collect();
+                if spots.is_empty() { continue; }
+
+                for other in layout.node_regions(spots[0]).iter().filter(|x| **x != i) {
+                    let other = &layout.regions()[*other];
+                    if !spots.iter().all(|pos| other.contains(*pos)) { continue; }
+
+                    for pos in &other.cells {
+                        if !region.contains(*pos) {
+                            change |= self.eliminate(*pos, num);
+                        }
+                    }
+                }
+            }
+        }
+
+        change
+    }
+
+    pub fn loop_intersections(&mut self) {
+        while self.solve_intersections() {}
+    }
+
+    ///The possibilities of a node, found nodes have none.
+    fn possible(&self, pos: Coord) -> Candidates {
+        self.nodes.get(pos).possibilities().copied().unwrap_or_default()
+    }
+
+    ///True if the node is unsolved and can still be the number.
+    fn can_be(&self, pos: Coord, num: usize) -> bool {
+        self.nodes.get(pos).possibilities().is_some_and(|list| list[num])
+    }
+
+    ///Naked subsets: when `size` unsolved nodes in a group only have `size` possibilities between them,
+    ///no other node in the group can be one of them. returns true if any possibilities were removed.
+    pub fn solve_naked_subsets(&mut self, size: usize) -> bool {
+        let mut change = false;
+
+        let layout = Rc::clone(&self.layout);
+
+        for region in layout.regions() {
+            let cells: Vec<Coord> = region.cells.iter().copied().filter(|pos| !self.nodes.get(*pos).is_found()).collect();
+            if cells.len() <= size { continue; }
+
+            for subset in combinations(cells.len(), size) {
+                let nums = subset.iter()
+                    .fold(Candidates::empty(), |nums, i| nums.union(self.possible(cells[*i])));
+
+                if nums.count() != size { continue; }
+
+                for (i, pos) in cells.iter().enumerate() {
+                    if subset.contains(&i) { continue; }
+
+                    for num in nums.iter() {
+                        change |= self.eliminate(*pos, num);
+                    }
+                }
+            }
+        }
+
+        change
+    }
+
+    ///Hidden subsets: when `size` numbers can only go in `size` nodes of a group,
+    ///those nodes can't be anything else. returns true if any possibilities were removed.
+    pub fn solve_hidden_subsets(&mut self, size: usize) -> bool {
+        let mut change = false;
+
+        let layout = Rc::clone(&self.layout);
+        let numbers = self.size;
+
+        for region in layout.regions().iter().filter(|x| x.is_full(numbers)) {
+            let cells: Vec<Coord> = region.cells.iter().copied().filter(|pos| !self.nodes.get(*pos).is_found()).collect();
+            if cells.len() <= size { continue; }
+
+            // for each number, the nodes it can go in.
+            let mut places: Vec<Candidates> = vec![Candidates::empty(); self.size];
+            for (i, pos) in cells.iter().enumerate() {
+                for num in self.possible(*pos).iter() {
+                    places[num].insert(i);
+                }
+            }
+
+            let nums: Vec<usize> = (0..self.size).filter(|num| !places[*num].is_empty()).collect();
+            if nums.len() <= size { continue; }
+
+            for subset in combinations(nums.len(), size) {
+                let spots = subset.iter().fold(Candidates::empty(), |spots, i| spots.union(places[nums[*i]]));
+
+                if spots.count() != size { continue; }
+
+                for i in spots.iter() {
+                    for (j, num) in nums.iter().enumerate() {
+                        if subset.contains(&j) { continue; }
+
+                        change |= self.eliminate(cells[i], *num);
+                    }
+                }
+            }
+        }
+
+        change
+    }
+
+    ///Naked then hidden subsets of two, three and four nodes. returns true if any possibilities were removed.
+    pub fn solve_subsets(&mut self) -> bool {
+        let mut change = false;
+
+        for size in 2..=4 {
+            change |= self.solve_naked_subsets(size);
+            change |= self.solve_hidden_subsets(size);
+        }
+
+        change
+    }
+
+    pub fn loop_subsets(&mut self) {
+        while self.solve_subsets() {}
+    }
+
+    ///Killer cages: removes numbers that can't be part of any set adding up to a cage's sum, then does
+    ///the same for the innies of every region that needs each number, the nodes left once the cages
+    ///inside the region are taken out. When those innies all belong to cages sticking out of the region
+    ///and only one node sticks out, that node has to make up the difference.
+    ///returns true if any possibilities were removed.
+    pub fn solve_cages(&mut self) -> bool {
+        if self.cages().is_empty() { return false }
+
+        let layout = Rc::clone(&self.layout);
+        let numbers = self.size;
+        let total = numbers * (numbers + 1) / 2;
+        let mut change = false;
+
+        for cage in layout.cages() {
+            change |= self.limit_sum(&cage.cells, cage.sum);
+        }
+
+        for region in layout.regions().iter().filter(|x| x.is_full(numbers)) {
+            let (inside, across): (Vec<&Cage>, Vec<&Cage>) = layout.cages().iter()
+                .filter(|cage| cage.cells.iter().any(|pos| region.contains(*pos)))
+                .partition(|cage| cage.cells.iter().all(|pos| region.contains(*pos)));
+
+            if inside.is_empty() && across.is_empty() { continue; }
+
+            let inner: usize = inside.iter().map(|x| x.sum).sum();
+            let Some(innie_sum) = total.checked_sub(inner) else { continue };
+
+            let innies: Vec<Coord> = region.cells.iter()
+                .copied()
+                .filter(|pos| !inside.iter().any(|x| x.contains(*pos)))
+                .collect();
+
+            if innies.is_empty() { continue; }
+
+            if !inside.is_empty() {
+                change |= self.limit_sum(&innies, innie_sum);
+            }
+
+            if !innies.iter().all(|pos| across.iter().any(|x| x.contains(*pos))) { continue; }
+
+            let outies: Vec<Coord> = across.iter()
+                .flat_map(|x| x.cells.iter().copied())
+                .filter(|pos| !region.contains(*pos))
+                .collect();
+
+            let outer: usize = across.iter().map(|x| x.sum).sum();
+
+            if let [pos] = outies.as_slice() {
+                for num in self.possible(*pos).iter() {
+                    if num + 1 + innie_sum != outer {
+                        change |= self.eliminate(*pos, num);
+                    }
+                }
+            }
+        }
+
+        change
+    }
+
+    pub fn loop_cages(&mut self) {
+        while self.solve_cages() {}
+    }
+
+    ///Edge marks: removes the numbers of a node that none of the numbers left for a neighbour
+    ///fit with, across a mark or a missing negative mark. returns true if any possibilities were removed.
+    pub fn solve_edges(&mut self) -> bool {
+        if self.edges().is_empty() && self.negative().is_empty() { return false }
+
+        let layout = Rc::clone(&self.layout);
+        let mut change = false;
+
+        for pos in self.all_iter() {
+            if self.nodes.get(pos).is_found() { continue; }
+
+            for check in layout.linked(pos) {
+                let options = self.options(check);
+
+                for num in self.possible(pos).iter() {
+                    if !options.iter().any(|other| layout.broken_mark(pos, num, check, other).is_none()) {
+                        change |= self.eliminate(pos, num);
+                    }
+                }
+            }
+        }
+
+        change
+    }
+
+    pub fn loop_edges(&mut self) {
+        while self.solve_edges() {}
+    }
+
+    ///The number of a found node, or the possibilities of one that isn't.
+    fn options(&self, pos: Coord) -> Candidates {
+        match self.nodes.get(pos).get_num() {
+            Some(num) => {
+                let mut found = Candidates::empty();
+                found.insert(num);
+                found
+            }
+            None => { self.possible(pos) }
+        }
+    }
+
+    ///Removes the possibilities of nodes that need different numbers adding up to `sum`
+    ///which aren't in any set that can make it. returns true if any possibilities were removed.
+    fn limit_sum(&mut self, cells: &[Coord], sum: usize) -> bool {
+        let options: Vec<Candidates> = cells.iter().map(|pos| self.options(*pos)).collect();
+
+        let Some(allowed) = sum_options(&options, sum, self.size) else { return false };
+        let mut change = false;
+
+        for (pos, allowed) in cells.iter().zip(allowed) {
+            for num in self.possible(*pos).difference(allowed).iter() {
+                change |= self.eliminate(*pos, num);
+            }
+        }
+
+        change
+    }
+
+    ///True if the found nodes of a cage already add up to too much, or leave a sum the rest can't make.
+    fn cage_broken(&self, cage: &Cage) -> bool {
+        let found: Vec<usize> = cage.cells.iter().filter_map(|pos| self.nodes.get(*pos).get_num()).collect();
+        let sum: usize = found.iter().map(|num| num + 1).sum();
+
+        if sum > cage.sum { return true }
+
+        let (min, max) = sum_range(cage.cells.len() - found.len(), self.size);
+        let left = cage.sum - sum;
+
+        left < min || left > max
+    }
+
+    ///Returns true if any node conflicts with another or has run out of possibilities,
+    ///or a cage can't make its sum.
+    pub fn has_conflicts(&self) -> bool {
         for pos in self.all_iter() {
             if self.nodes.get(pos).has_problems() { return true; }
         }
 
-        false
+        self.cages().iter().any(|x| self.cage_broken(x))
     }
 
     ///return false if if encountered conflicts.
     pub fn full_solve_no_guessing(&mut self) -> bool {
+        loop {
+            if !self.solve_singles() { return false; }
+            if self.solve_intersections() { continue; }
+            if self.solve_subsets() { continue; }
+            
+            return true;
+        }
+    }
+
+    ///Basic, exclusive, cage and edge solves until none of them make progress. returns false if a conflict was found.
+    fn solve_singles(&mut self) -> bool {
         loop {
             if self.has_conflicts() { return false; }
 
             if self.solve_basic() { continue; }
             if self.solve_exclusive() { continue; }
-            
+            if self.solve_cages() { continue; }
+            if self.solve_edges() { continue; }
+
             return true;
         }
     }
@@ -318,22 +955,14 @@ impl State {
 
     fn guess(&mut self) -> bool {
         if let Some(pos) = self.guess_candidate() {
-            let mut solve = None;
-
-            for (i, possible) in self.nodes.get(pos).possibilities().unwrap().iter().enumerate() {
-                if *possible {
-                    solve = Some(i);
-                    break;
-                }
-            }
-
-            let solve = solve.unwrap();
+            let solve = self.possible(pos).first().unwrap();
 
             self.inc_guess();
 
             self.set(pos, solve);
             self.nodes.get_mut(pos).set_guess_id(self.guesses.len());
             self.guesses.push(pos);
+            self.guess_count += 1;
 
             true
         }
@@ -348,20 +977,14 @@ impl State {
                 let last = self.nodes.get(pos).solution().unwrap();
                 self.dec_guess();
 
-                let mut solve = None;
-
-                for (i, possible) in self.nodes.get(pos).possibilities().unwrap().iter().enumerate().skip(last + 1) {
-                    if *possible {
-                        solve = Some(i);
-                        break;
-                    }
-                }
+                let solve = self.possible(pos).iter().find(|x| *x > last);
 
                 if let Some(num) = solve {
                     self.inc_guess();
                     self.set(pos, num);
                     self.nodes.get_mut(pos).set_guess_id(self.guesses.len());
                     self.guesses.push(pos);
+                    self.guess_count += 1;
                 }
                 else {
                     continue;
@@ -376,20 +999,24 @@ impl State {
     }
 
     pub fn full_solve(&mut self) -> bool {
+        let mut possible = self.full_solve_no_guessing();
+
         loop {
-            if self.full_solve_no_guessing() {
-                if self.guess() { continue; }
+            if possible {
+                if !self.guess() { return self.finished.done(); }
             }
             else {
-                if self.retry_guess() { continue; }
+                if !self.retry_guess() { return self.finished.done(); }
             }
 
-            return self.finished.done();
+            // subsets are too slow to search after every guess, singles are enough to test them.
+            possible = self.solve_singles();
         }
     }
 
     pub fn check_if_possible(&mut self) -> bool {
         let guess = self.guess_level;
+        let guesses = std::mem::take(&mut self.guesses);
 
         self.inc_guess();
         let result = self.full_solve();
@@ -398,51 +1025,130 @@ impl State {
             self.dec_guess();
         }
 
+        self.guesses = guesses;
         result
     }
-}
 
-fn all_iter(size: i32) -> CoordIter {
-    CoordIter::new(
-        Coord { x: 0, y: 0 },
-        Coord { x: size, y: size }
-    )
-}
+    ///Counts the ways the puzzle can be finished, stopping once the limit is reached.
+    ///Returns the count along with the first two solutions found. The puzzle is left as it was.
+    pub fn count_solutions(&mut self, limit: usize) -> (usize, Vec<Vec2D<usize>>) {
+        let guess = self.guess_level;
+        let guesses = std::mem::take(&mut self.guesses);
+        let mut count = 0;
+        let mut found = Vec::new();
 
-fn row_iter(size: i32, y: i32) -> CoordIter {
-    CoordIter::new(
-        Coord { x: 0, y },
-        Coord { x: size, y: y + 1 }
-    )
-}
+        self.inc_guess();
+        let mut solved = self.full_solve() && !self.has_conflicts();
+
+        while solved {
+            count += 1;
 
-fn column_iter(size: i32, x: i32) -> CoordIter {
-    CoordIter::new(
-        Coord { x, y: 0 },
-        Coord { x: x + 1, y: size }
-    )
+            if found.len() < 2 {
+                found.push(self.solution_grid());
+            }
+
+            if count >= limit { break; }
+
+            // move on from the last guess, the next solution has to be in a branch that hasn't been tried yet.
+            solved = self.retry_guess() && self.full_solve();
+        }
+
+        while self.guess_level > guess {
+            self.dec_guess();
+        }
+
+        self.guesses = guesses;
+        (count, found)
+    }
+
+    pub fn solutions(&mut self) -> Solutions {
+        Solutions::new(self.count_solutions(2).1)
+    }
+
+    fn solution_grid(&self) -> Vec2D<usize> {
+        let mut grid = Vec2D::new(self.nodes.size(), 0);
+
+        for pos in self.all_iter() {
+            *grid.get_mut(pos) = self.nodes.get(pos).get_num().unwrap_or(0);
+        }
+
+        grid
+    }
 }
 
-fn block_iter(blocks: Coord, pos: Coord) -> CoordIter {
-    let x = pos.x * blocks.y;
-    let y = pos.y * blocks.x;
+impl Undo for State {
+    type Change = Change;
+
+    fn changes_from(&self, before: &Self) -> Option<Change> {
+        let nodes: Vec<_> = self.all_iter()
+            .filter(|x| self.nodes.get(*x) != before.nodes.get(*x))
+            .map(|x| (x, before.nodes.get(x).clone()))
+            .collect();
+
+        let eliminated: Vec<_> = self.all_iter()
+            .filter(|x| self.eliminated.get(*x) != before.eliminated.get(*x))
+            .map(|x| (x, before.eliminated.get(x).clone()))
+            .collect();
+
+        let marks: Vec<_> = self.all_iter()
+            .filter(|x| self.marks.get(*x) != before.marks.get(*x))
+            .map(|x| (x, *before.marks.get(x)))
+            .collect();
+
+        // the layout is shared until it is changed, so a new one means the blocks or constraints changed.
+        let same = nodes.is_empty()
+            && eliminated.is_empty()
+            && marks.is_empty()
+            && self.guess_level == before.guess_level
+            && self.guesses == before.guesses
+            && self.guess_count == before.guess_count
+            && Rc::ptr_eq(&self.layout, &before.layout);
+
+        if same { return None }
+
+        Some(Change {
+            nodes,
+            eliminated,
+            marks,
+            finished:    before.finished.clone(),
+            guess_level: before.guess_level,
+            guesses:     before.guesses.clone(),
+            guess_count: before.guess_count,
+            layout:      Rc::clone(&before.layout),
+        })
+    }
 
-    CoordIter::new(
-        Coord { x, y },
-        Coord { x: x + blocks.y, y: y + blocks.x }
-    )
+    fn revert(&mut self, change: Change) -> Change {
+        Change {
+            nodes:       change.nodes.into_iter().map(|(pos, x)| (pos, std::mem::replace(self.nodes.get_mut(pos), x))).collect(),
+            eliminated:  change.eliminated.into_iter().map(|(pos, x)| (pos, std::mem::replace(self.eliminated.get_mut(pos), x))).collect(),
+            marks:       change.marks.into_iter().map(|(pos, x)| (pos, std::mem::replace(self.marks.get_mut(pos), x))).collect(),
+            finished:    std::mem::replace(&mut self.finished, change.finished),
+            guess_level: std::mem::replace(&mut self.guess_level, change.guess_level),
+            guesses:     std::mem::replace(&mut self.guesses, change.guesses),
+            guess_count: std::mem::replace(&mut self.guess_count, change.guess_count),
+            layout:      std::mem::replace(&mut self.layout, change.layout),
+        }
+    }
 }
 
-fn iter_iter(size: i32, blocks: Coord) -> impl Iterator<Item = CoordIter> {
-    let rows = (0..size).map(move |y| row_iter(size, y));
-    let column = (0..size).map(move |x| column_iter(size, x));
+///Every way to choose `k` indices out of `n`, in increasing order.
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    let mut all = Vec::new();
+    let mut cur: Vec<usize> = (0..k).collect();
+    if k > n { return all }
 
-    let blocks = CoordIter::new(
-        Coord{ x: 0, y: 0,},
-        blocks
-    ).map(move |x| block_iter(blocks, x));
+    loop {
+        all.push(cur.clone());
 
-    rows.chain(column).chain(blocks)
+        // find the rightmost index that can still move up.
+        let Some(i) = (0..k).rev().find(|i| cur[*i] < n - k + i) else { return all };
+
+        cur[i] += 1;
+        for j in i + 1..k {
+            cur[j] = cur[j - 1] + 1;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -464,17 +1170,14 @@ mod tests {
 
     #[test]
     fn test2(){
-        let mut state = State::new(Coord { x: 2, y: 2 });
+        let state = State::new(Coord { x: 2, y: 2 });
 
-        let mut i = 0;
-        for iter in state.iter_iter() {
-            println!("iter #{}:", i);
+        for (i, region) in state.regions().iter().enumerate() {
+            println!("region #{}: {}", i, region.unit.name());
 
-            for pos in iter {
+            for pos in &region.cells {
                 println!("{:?}", pos);
             }
-
-            i += 1;
         }
     }
 
@@ -559,4 +1262,303 @@ mod tests {
         let mut state = State::new(Coord { x: 2, y: 2 });
         state.full_solve();
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn count_solutions() {
+        let mut state = State::new(Coord { x: 2, y: 2 });
+        assert_eq!(state.count_solutions(1000).0, 288);
+        assert_eq!(state.finished(), 0);
+
+        state.set(Coord { x: 0, y: 0 }, 0);
+        state.set(Coord { x: 1, y: 0 }, 1);
+        state.set(Coord { x: 2, y: 0 }, 2);
+        assert!(matches!(state.solutions(), Solutions::Multiple(_, _)));
+
+        state.set(Coord { x: 0, y: 1 }, 2);
+        state.set(Coord { x: 1, y: 2 }, 0);
+        state.set(Coord { x: 2, y: 3 }, 1);
+        assert!(matches!(state.solutions(), Solutions::Unique(_)));
+        assert_eq!(state.finished(), 6);
+
+        state.set(Coord { x: 3, y: 3 }, 2);
+        assert!(matches!(state.solutions(), Solutions::None));
+    }
+
+    #[test]
+    fn subsets() {
+        let mut state = State::new(Coord { x: 3, y: 3 });
+
+        // the first two nodes of the top row can only be 1 or 2.
+        for x in 0..2 {
+            for num in 2..9 {
+                state.eliminate(Coord { x, y: 0 }, num);
+            }
+        }
+
+        assert!(state.solve_naked_subsets(2));
+        for x in 2..9 {
+            let list = state.nodes.get(Coord { x, y: 0 }).possibilities().unwrap();
+            assert!(!list[0] && !list[1]);
+        }
+
+        // eliminations stay after possibilities are rechecked.
+        state.set(Coord { x: 8, y: 8 }, 8);
+        assert!(!state.nodes.get(Coord { x: 5, y: 0 }).possibilities().unwrap()[0]);
+
+        // 3 and 4 can only go in the first two nodes of the second row within the first block.
+        for pos in [Coord { x: 2, y: 0 }, Coord { x: 2, y: 1 }, Coord { x: 0, y: 2 }, Coord { x: 1, y: 2 }, Coord { x: 2, y: 2 }] {
+            state.eliminate(pos, 2);
+            state.eliminate(pos, 3);
+        }
+
+        assert!(state.solve_hidden_subsets(2));
+        for x in 0..2 {
+            let list = state.nodes.get(Coord { x, y: 1 }).possibilities().unwrap();
+            assert_eq!(list.count(), 2);
+            assert!(list[2] && list[3]);
+        }
+    }
+
+    #[test]
+    fn intersections() {
+        // blocks three wide and two tall.
+        let mut state = State::new(Coord { x: 3, y: 2 });
+
+        // in the first block, 1 can only go in the top row.
+        for x in 0..3 {
+            state.eliminate(Coord { x, y: 1 }, 0);
+        }
+
+        assert!(state.solve_intersections());
+        for x in 3..6 {
+            assert!(!state.can_be(Coord { x, y: 0 }, 0));
+        }
+        assert!(state.can_be(Coord { x: 3, y: 1 }, 0));
+
+        // in the second column, 2 can only go in the bottom block.
+        for y in 0..4 {
+            state.eliminate(Coord { x: 1, y }, 1);
+        }
+
+        assert!(state.solve_intersections());
+        for pos in [Coord { x: 0, y: 4 }, Coord { x: 2, y: 5 }] {
+            assert!(!state.can_be(pos, 1));
+        }
+        assert!(state.can_be(Coord { x: 1, y: 5 }, 1));
+        assert!(state.can_be(Coord { x: 3, y: 4 }, 1));
+    }
+
+    #[test]
+    fn eliminations() {
+        let mut state = State::new(Coord { x: 2, y: 2 });
+        let pos = Coord { x: 0, y: 0 };
+
+        state.eliminate(pos, 0);
+        state.inc_guess();
+        state.eliminate(pos, 1);
+        state.set(Coord { x: 3, y: 3 }, 3);
+
+        // kept through setting and clearing other nodes.
+        state.clear(Coord { x: 3, y: 3 });
+        assert!(!state.can_be(pos, 0) && !state.can_be(pos, 1));
+        assert_eq!(state.eliminations().len(), 2);
+
+        // only the elimination made at the higher level comes back.
+        state.dec_guess();
+        assert!(!state.can_be(pos, 0) && state.can_be(pos, 1));
+        assert_eq!(state.eliminations(), vec![(pos, 0, 0)]);
+    }
+
+    #[test]
+    fn extra_regions() {
+        let mut state = State::new(Coord { x: 2, y: 2 });
+        let cells = [Coord { x: 0, y: 0 }, Coord { x: 2, y: 1 }, Coord { x: 1, y: 3 }, Coord { x: 3, y: 2 }];
+
+        state.set(cells[0], 0);
+        state.set(cells[3], 0);
+        assert!(!state.has_conflicts());
+        assert!(state.can_be(cells[1], 0));
+
+        // the new region sees both nodes, so they now conflict and rule 0 out for the rest of it.
+        state.add_region(Region::new(Unit::Block(4), cells));
+        assert!(state.has_conflicts());
+        assert!(!state.can_be(cells[1], 0));
+        assert_eq!(state.peers(cells[1]).len(), 10);
+
+        state.clear(cells[3]);
+        assert!(!state.has_conflicts());
+        assert!(state.can_be(cells[3], 1));
+    }
+
+    #[test]
+    fn cages() {
+        let mut state = State::new(Coord { x: 2, y: 2 });
+        let pair = Cage::new(3, [Coord { x: 0, y: 1 }, Coord { x: 1, y: 1 }]);
+
+        state.add_cage(Cage::new(9, [Coord { x: 0, y: 0 }, Coord { x: 1, y: 0 }, Coord { x: 2, y: 0 }]));
+        state.add_cage(pair.clone());
+        assert!(state.solve_cages());
+
+        // only 1 and 2 make 3.
+        for pos in &pair.cells {
+            assert_eq!(state.possible(*pos).iter().collect::<Vec<_>>(), vec![0, 1]);
+        }
+
+        // the pair leaves 7 for the top of the first block, so the node sticking out is 2,
+        // and the rest of the first row is the 1 the cage doesn't use.
+        assert_eq!(state.possible(Coord { x: 2, y: 0 }).iter().collect::<Vec<_>>(), vec![1]);
+        assert_eq!(state.possible(Coord { x: 3, y: 0 }).iter().collect::<Vec<_>>(), vec![0]);
+        assert_eq!(state.regions().last().unwrap().unit, Unit::Cage(1));
+
+        assert!(!state.has_conflicts());
+        state.set(pair.cells[0], 2);
+        assert!(state.has_conflicts());
+
+        state.clear(pair.cells[0]);
+        assert!(state.count_solutions(10).1.iter().all(|grid| {
+            *grid.get(Coord { x: 0, y: 1 }) + *grid.get(Coord { x: 1, y: 1 }) + 2 == 3
+        }));
+    }
+
+    #[test]
+    fn chess() {
+        let mut state = State::new(Coord { x: 3, y: 3 });
+        let pos = Coord { x: 2, y: 2 };
+        let knight = Coord { x: 3, y: 4 };
+
+        state.set(pos, 0);
+        state.set(knight, 0);
+        assert!(!state.has_conflicts());
+
+        state.add_chess(Chess::Knight);
+        assert!(state.has_conflicts());
+        assert_eq!(state.nodes().get(pos).conflicts(), Some(&vec![knight]));
+        assert_eq!(state.relation(pos, knight).as_deref(), Some("knight's move"));
+        assert_eq!(state.relation(pos, Coord { x: 7, y: 2 }).as_deref(), Some("row 3"));
+
+        state.clear(knight);
+        assert!(!state.has_conflicts());
+        assert!(!state.can_be(Coord { x: 4, y: 3 }, 0));
+
+        // a king's move inside a block is already covered by the block.
+        state.add_chess(Chess::King);
+        state.add_chess(Chess::King);
+        assert_eq!(state.chess(), &[Chess::Knight, Chess::King]);
+        assert_eq!(state.relation(pos, Coord { x: 1, y: 1 }).as_deref(), Some("block 1"));
+        assert_eq!(state.relation(pos, Coord { x: 3, y: 3 }).as_deref(), Some("king's move"));
+    }
+
+    #[test]
+    fn edges() {
+        let mut state = State::new(Coord { x: 3, y: 3 });
+        let (a, b) = (Coord { x: 0, y: 0 }, Coord { x: 1, y: 0 });
+
+        state.add_edge(Edge::new(Mark::V, a, b));
+        state.set(a, 0);
+        state.set(b, 4);
+        assert!(state.has_conflicts());
+        assert_eq!(state.nodes().get(a).conflicts(), Some(&vec![b]));
+        assert_eq!(state.relation(a, b).as_deref(), Some("V"));
+
+        // 1 and 4 make 5.
+        state.set(b, 3);
+        assert!(!state.has_conflicts());
+
+        // only 7 goes with 3 across an X, and only numbers below 9 can be smaller than a neighbour.
+        let (c, d) = (Coord { x: 4, y: 4 }, Coord { x: 4, y: 5 });
+        state.add_edge(Edge::new(Mark::X, Coord { x: 0, y: 1 }, Coord { x: 0, y: 2 }));
+        state.add_edge(Edge::new(Mark::Greater, c, d));
+        state.set(Coord { x: 0, y: 1 }, 2);
+        state.loop_edges();
+
+        assert_eq!(state.possible(Coord { x: 0, y: 2 }).iter().collect::<Vec<_>>(), vec![6]);
+        assert!(!state.can_be(c, 0));
+        assert!(!state.can_be(d, 8));
+
+        // without a white dot, the neighbours of a 5 can't be 4 or 6.
+        let e = Coord { x: 7, y: 7 };
+        state.set(e, 4);
+        state.add_edge(Edge::new(Mark::White, e, Coord { x: 7, y: 8 }));
+        state.set_negative(&[Mark::White, Mark::Greater]);
+        state.loop_edges();
+
+        assert_eq!(state.negative(), &[Mark::White]);
+        assert!(!state.can_be(Coord { x: 8, y: 7 }, 3));
+        assert!(!state.can_be(Coord { x: 8, y: 7 }, 5));
+        assert_eq!(state.possible(Coord { x: 7, y: 8 }).iter().collect::<Vec<_>>(), vec![3, 5]);
+
+        state.set(Coord { x: 6, y: 7 }, 5);
+        assert_eq!(state.relation(e, Coord { x: 6, y: 7 }).as_deref(), Some("no white dot"));
+    }
+
+    #[test]
+    fn windows() {
+        let mut state = State::new(Coord { x: 3, y: 3 });
+        let (a, b) = (Coord { x: 3, y: 1 }, Coord { x: 1, y: 3 });
+
+        state.set(a, 0);
+        state.set(b, 0);
+        assert!(!state.has_conflicts());
+
+        // both are in the first window, but no row, column or block.
+        state.add_windows();
+        assert!(state.is_windoku());
+        assert!(state.has_conflicts());
+        assert_eq!(state.relation(a, b).as_deref(), Some("window 1"));
+
+        let windows: Vec<&Region> = state.regions().iter().filter(|x| matches!(x.unit, Unit::Window(_))).collect();
+        assert_eq!(windows.len(), 4);
+        assert_eq!(windows[3].cells[0], Coord { x: 5, y: 5 });
+        assert_eq!(Region::windows(4).len(), 9);
+        assert_eq!(Region::windows(4)[8].cells.last(), Some(&Coord { x: 14, y: 14 }));
+    }
+
+    #[test]
+    fn marks() {
+        let mut state = State::new(Coord { x: 2, y: 2 });
+        let pos = Coord { x: 1, y: 2 };
+
+        state.toggle_mark(pos, 0);
+        state.toggle_mark(pos, 3);
+        state.toggle_mark(pos, 0);
+        assert_eq!(state.marks(pos).iter().collect::<Vec<_>>(), vec![3]);
+
+        state.add_mark(pos, 3);
+        assert!(state.marks(pos).has(3));
+
+        // marks are the user's own, the solvers and found nodes leave them alone.
+        state.set(Coord { x: 0, y: 2 }, 3);
+        assert!(state.marks(pos).has(3));
+        assert!(state.marks(Coord { x: 0, y: 0 }).is_empty());
+    }
+
+    #[test]
+    fn incremental_possibilities() {
+        let mut state = State::new(Coord { x: 3, y: 2 });
+        let mut random = crate::random::Random::new(7);
+
+        for step in 0..300 {
+            let pos = Coord { x: random.below(6) as i32, y: random.below(6) as i32 };
+
+            match random.below(5) {
+                0 => { state.clear(pos); }
+                1 => { state.inc_guess(); }
+                2 => { state.dec_guess(); }
+                3 => { state.eliminate(pos, random.below(6)); }
+                _ => { state.set(pos, random.below(6)); }
+            }
+
+            // updating only the nodes around a change should match rebuilding everything.
+            let mut rebuilt = state.clone();
+            rebuilt.reset_possible();
+
+            for check in state.all_iter() {
+                assert_eq!(
+                    state.nodes.get(check).possibilities(),
+                    rebuilt.nodes.get(check).possibilities(),
+                    "step {}", step
+                );
+            }
+        }
+    }
+}